@@ -0,0 +1,49 @@
+// Instruction throughput of the core, run with `cargo bench`.
+
+use chip8_core::Emu;
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+
+// Instructions run per iteration.
+const INSTRUCTIONS: u64 = 10_000;
+
+// CPU-bound loop that never touches the screen, timers or keys so only opcode dispatch and execution are measured:
+// arithmetic and logic on a few registers, a memory store and load, and a skip and jump back to the start.
+const LOOP: [u8; 22] = [
+    0x60, 0x01, // 200: LD V0, 0x01
+    0x71, 0x01, // 202: ADD V1, 0x01
+    0x82, 0x14, // 204: ADD V2, V1
+    0x83, 0x23, // 206: XOR V3, V2
+    0x84, 0x06, // 208: SHR V4
+    0xA3, 0x00, // 20A: LD I, 0x300
+    0xF2, 0x55, // 20C: LD [I], V2
+    0xF2, 0x65, // 20E: LD V2, [I]
+    0x35, 0x00, // 210: SE V5, 0x00
+    0x00, 0xE0, // 212: CLS, always skipped
+    0x12, 0x00, // 214: JP 0x200
+];
+
+fn tick(c: &mut Criterion) {
+    let mut group = c.benchmark_group("tick");
+    group.throughput(Throughput::Elements(INSTRUCTIONS));
+
+    group.bench_function("alu loop", |b| {
+        b.iter_batched_ref(
+            || {
+                let mut emu = Emu::new();
+                emu.load(&LOOP).unwrap();
+                emu
+            },
+            |emu| {
+                for _ in 0..INSTRUCTIONS {
+                    black_box(emu.tick()).unwrap();
+                }
+            },
+            BatchSize::SmallInput,
+        )
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, tick);
+criterion_main!(benches);
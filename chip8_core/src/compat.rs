@@ -0,0 +1,237 @@
+use crate::decode::{decode, Instruction};
+use crate::{Emu, Quirks, START_ADDR};
+
+// The platform a ROM was most likely written for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CompatPreset {
+    CosmacVip,
+    Chip48,
+    SuperChipModern,
+    XoChip,
+}
+
+impl CompatPreset {
+    // The following preset in declaration order, wrapping around after XoChip. Lets frontends cycle through them.
+    pub fn next(self) -> Self {
+        match self {
+            CompatPreset::CosmacVip => CompatPreset::Chip48,
+            CompatPreset::Chip48 => CompatPreset::SuperChipModern,
+            CompatPreset::SuperChipModern => CompatPreset::XoChip,
+            CompatPreset::XoChip => CompatPreset::CosmacVip,
+        }
+    }
+}
+
+// Guess which platform a ROM targets from the instructions it uses.
+// This is a heuristic: only code reachable from the entry point is inspected, following jumps, calls and skips,
+// so sprite data is not mistaken for instructions. Computed jumps (BNNN) are not followed.
+// - Any XO-CHIP only instruction (F000 NNNN, FN01, F002, FX3A, 5XY2, 5XY3) means XO-CHIP.
+// - Any SUPER-CHIP only instruction (00CN, 00FB-00FF, DXY0, FX30, FX75, FX85) means SUPER-CHIP.
+// - Logic instructions (8XY1, 8XY2, 8XY3) mean COSMAC VIP, since those games tend to rely on VF being reset by them.
+// - Anything else defaults to CHIP-48.
+pub fn detect_preset(rom: &[u8]) -> CompatPreset {
+    let mut visited = vec![false; rom.len()];
+    let mut pending = vec![0];
+    let mut superchip = false;
+    let mut logic = false;
+
+    while let Some(offset) = pending.pop() {
+        // Stop on anything outside the ROM, misaligned with it, or already inspected.
+        if offset + 1 >= rom.len() || visited[offset] {
+            continue;
+        }
+
+        visited[offset] = true;
+        let op = ((rom[offset] as u16) << 8) | rom[offset + 1] as u16;
+        // Jumps below the start address lead out of the ROM and aren't followed.
+        let target = |addr: u16| (addr as usize).checked_sub(START_ADDR as usize);
+        let next = offset + 2;
+
+        let Some(instruction) = decode(op) else {
+            // The core doesn't implement 5XY2, 5XY3 and FX30, but they still give the platform away.
+            if op & 0xF00E == 0x5002 {
+                return CompatPreset::XoChip;
+            }
+            if op & 0xF0FF == 0xF030 {
+                superchip = true;
+            }
+            pending.push(next);
+            continue;
+        };
+
+        match instruction {
+            Instruction::SetILong
+            | Instruction::SelectPlanes(_)
+            | Instruction::LoadAudio
+            | Instruction::SetPitch(_) => {
+                return CompatPreset::XoChip;
+            }
+
+            Instruction::ScrollDown(_)
+            | Instruction::ScrollRight
+            | Instruction::ScrollLeft
+            | Instruction::Exit
+            | Instruction::LowRes
+            | Instruction::HighRes
+            | Instruction::Draw { n: 0, .. }
+            | Instruction::StoreFlags(_)
+            | Instruction::LoadFlags(_) => {
+                superchip = true;
+                pending.push(next);
+            }
+
+            Instruction::Or { .. } | Instruction::And { .. } | Instruction::Xor { .. } => {
+                logic = true;
+                pending.push(next);
+            }
+
+            // Return and computed jump end this path.
+            Instruction::Return | Instruction::JumpOffset { .. } => (),
+
+            Instruction::Jump(nnn) => pending.extend(target(nnn)),
+
+            Instruction::Call(nnn) => {
+                pending.extend(target(nnn));
+                pending.push(next);
+            }
+
+            // Skips continue at either of the next two instructions.
+            Instruction::SkipIfEqual { .. }
+            | Instruction::SkipIfNotEqual { .. }
+            | Instruction::SkipIfRegsEqual { .. }
+            | Instruction::SkipIfRegsNotEqual { .. }
+            | Instruction::SkipIfKey(_)
+            | Instruction::SkipIfNotKey(_) => {
+                pending.push(next);
+                pending.push(next + 2);
+            }
+
+            _ => pending.push(next),
+        }
+    }
+
+    if superchip {
+        CompatPreset::SuperChipModern
+    } else if logic {
+        CompatPreset::CosmacVip
+    } else {
+        CompatPreset::Chip48
+    }
+}
+
+// Quirk combinations of the common platforms, applied with `Emu::apply_profile`:
+//
+//   quirk                    Chip8  Chip48  SuperChip  XoChip
+//   shift_uses_vy            yes    no      no         yes
+//   load_store_increments_i  yes    no      no         yes
+//   large_sprites            no     no      yes        yes
+//   display_wait             yes    no      no         no
+//   wrap_x / wrap_y          no     no      no         yes
+//   jump_uses_vx             no     yes     yes        no
+//
+// XO-CHIP games may also need extended RAM, which is chosen when building the emulator.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Profile {
+    // The original interpreter on the COSMAC VIP.
+    Chip8,
+    // CHIP-48 on the HP-48 calculators.
+    Chip48,
+    // SUPER-CHIP 1.1 as modern games expect it.
+    SuperChip,
+    XoChip,
+}
+
+impl Profile {
+    pub fn quirks(self) -> Quirks {
+        match self {
+            Profile::Chip8 => Quirks {
+                shift_uses_vy: true,
+                load_store_increments_i: true,
+                large_sprites: false,
+                display_wait: true,
+                wrap_x: false,
+                wrap_y: false,
+                jump_uses_vx: false,
+            },
+            Profile::Chip48 => Quirks {
+                shift_uses_vy: false,
+                load_store_increments_i: false,
+                large_sprites: false,
+                display_wait: false,
+                wrap_x: false,
+                wrap_y: false,
+                jump_uses_vx: true,
+            },
+            Profile::SuperChip => Quirks {
+                shift_uses_vy: false,
+                load_store_increments_i: false,
+                large_sprites: true,
+                display_wait: false,
+                wrap_x: false,
+                wrap_y: false,
+                jump_uses_vx: true,
+            },
+            Profile::XoChip => Quirks {
+                shift_uses_vy: true,
+                load_store_increments_i: true,
+                large_sprites: true,
+                display_wait: false,
+                wrap_x: true,
+                wrap_y: true,
+                jump_uses_vx: false,
+            },
+        }
+    }
+}
+
+// The profile matching a detected platform.
+impl From<CompatPreset> for Profile {
+    fn from(preset: CompatPreset) -> Self {
+        match preset {
+            CompatPreset::CosmacVip => Profile::Chip8,
+            CompatPreset::Chip48 => Profile::Chip48,
+            CompatPreset::SuperChipModern => Profile::SuperChip,
+            CompatPreset::XoChip => Profile::XoChip,
+        }
+    }
+}
+
+impl Emu {
+    // Replace all quirks with the profile's combination.
+    pub fn apply_profile(&mut self, profile: Profile) {
+        self.set_quirks(profile.quirks());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn presets_cycle_in_order() {
+        let mut preset = CompatPreset::CosmacVip;
+        let mut seen = Vec::new();
+        for _ in 0..5 {
+            seen.push(preset);
+            preset = preset.next();
+        }
+
+        assert_eq!(
+            seen,
+            [
+                CompatPreset::CosmacVip,
+                CompatPreset::Chip48,
+                CompatPreset::SuperChipModern,
+                CompatPreset::XoChip,
+                CompatPreset::CosmacVip,
+            ]
+        );
+    }
+
+    #[test]
+    fn jumps_below_the_start_address_are_ignored() {
+        // Jump to 0x1FF, and call 0x000 then use a SUPER-CHIP scroll.
+        assert_eq!(detect_preset(&[0x11, 0xFF]), CompatPreset::Chip48);
+        assert_eq!(detect_preset(&[0x20, 0x00, 0x00, 0xFB]), CompatPreset::SuperChipModern);
+    }
+}
@@ -0,0 +1,206 @@
+// Opcode decoding shared by execution, disassembly, explanations and platform detection, so they all agree on
+// which opcodes exist and what their operands are. Register operands are indices from 0 to 15.
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Instruction {
+    // 0000 - No operation.
+    Nop,
+    // 00E0 - Clear the selected planes.
+    Clear,
+    // 00CN - Scroll the screen down by N pixels.
+    ScrollDown(u8),
+    // 00FB - Scroll the screen right by 4 pixels.
+    ScrollRight,
+    // 00FC - Scroll the screen left by 4 pixels.
+    ScrollLeft,
+    // 00FD - Exit the interpreter.
+    Exit,
+    // 00FE - Switch to 64x32 low resolution.
+    LowRes,
+    // 00FF - Switch to 128x64 high resolution.
+    HighRes,
+    // 00EE - Return from subroutine.
+    Return,
+    // 1NNN - Jump to NNN.
+    Jump(u16),
+    // 2NNN - Call the subroutine at NNN.
+    Call(u16),
+    // 3XNN - Skip next if VX == NN.
+    SkipIfEqual { x: usize, nn: u8 },
+    // 4XNN - Skip next if VX != NN.
+    SkipIfNotEqual { x: usize, nn: u8 },
+    // 5XY0 - Skip next if VX == VY.
+    SkipIfRegsEqual { x: usize, y: usize },
+    // 6XNN - Set VX to NN.
+    Set { x: usize, nn: u8 },
+    // 7XNN - Add NN to VX without a carry.
+    AddValue { x: usize, nn: u8 },
+    // 8XY0 - Set VX to VY.
+    Copy { x: usize, y: usize },
+    // 8XY1 - VX |= VY.
+    Or { x: usize, y: usize },
+    // 8XY2 - VX &= VY.
+    And { x: usize, y: usize },
+    // 8XY3 - VX ^= VY.
+    Xor { x: usize, y: usize },
+    // 8XY4 - VX += VY with carry in VF.
+    Add { x: usize, y: usize },
+    // 8XY5 - VX -= VY with no borrow in VF.
+    Sub { x: usize, y: usize },
+    // 8XY6 - Shift right with the dropped bit in VF.
+    ShiftRight { x: usize, y: usize },
+    // 8XY7 - VX = VY - VX with no borrow in VF.
+    SubReverse { x: usize, y: usize },
+    // 8XYE - Shift left with the dropped bit in VF.
+    ShiftLeft { x: usize, y: usize },
+    // 9XY0 - Skip next if VX != VY.
+    SkipIfRegsNotEqual { x: usize, y: usize },
+    // ANNN - Set I to NNN.
+    SetI(u16),
+    // BNNN - Jump to V0 + NNN, or BXNN - Jump to VX + NN depending on the quirk.
+    JumpOffset { x: usize, nnn: u16 },
+    // CXNN - Set VX to a random number AND NN.
+    Random { x: usize, nn: u8 },
+    // DXYN - Draw an N row sprite at VX, VY.
+    Draw { x: usize, y: usize, n: u8 },
+    // EX9E - Skip next if the key in VX is pressed.
+    SkipIfKey(usize),
+    // EXA1 - Skip next if the key in VX is not pressed.
+    SkipIfNotKey(usize),
+    // F000 NNNN - Set I to the 16-bit address in the following word.
+    SetILong,
+    // F002 - Load the audio pattern from I.
+    LoadAudio,
+    // FN01 - Select the planes, N is a bit mask.
+    SelectPlanes(u8),
+    // FX07 - Set VX to the delay timer.
+    GetDelay(usize),
+    // FX0A - Wait for a key press into VX.
+    WaitKey(usize),
+    // FX15 - Set the delay timer to VX.
+    SetDelay(usize),
+    // FX18 - Set the sound timer to VX.
+    SetSound(usize),
+    // FX1E - Add VX to I.
+    AddI(usize),
+    // FX29 - Point I at the font sprite for VX.
+    SetIFont(usize),
+    // FX3A - Set the audio pitch to VX.
+    SetPitch(usize),
+    // FX33 - Store the decimal digits of VX at I.
+    StoreBcd(usize),
+    // FX55 - Store V0 - VX at I.
+    StoreRegs(usize),
+    // FX65 - Load V0 - VX from I.
+    LoadRegs(usize),
+    // FX75 - Store V0 - VX in the RPL user flags.
+    StoreFlags(usize),
+    // FX85 - Load V0 - VX from the RPL user flags.
+    LoadFlags(usize),
+}
+
+// Decode an opcode, None if the core doesn't handle it.
+pub fn decode(op: u16) -> Option<Instruction> {
+    let digit1 = (op & 0xF000) >> 12;
+    let digit2 = (op & 0x0F00) >> 8;
+    let digit3 = (op & 0x00F0) >> 4;
+    let digit4 = op & 0x000F;
+    let x = digit2 as usize;
+    let y = digit3 as usize;
+    let nn = (op & 0xFF) as u8;
+    let nnn = op & 0xFFF;
+
+    let instruction = match (digit1, digit2, digit3, digit4) {
+        (0, 0, 0, 0) => Instruction::Nop,
+        (0, 0, 0xE, 0) => Instruction::Clear,
+        (0, 0, 0xC, _) => Instruction::ScrollDown(digit4 as u8),
+        (0, 0, 0xF, 0xB) => Instruction::ScrollRight,
+        (0, 0, 0xF, 0xC) => Instruction::ScrollLeft,
+        (0, 0, 0xF, 0xD) => Instruction::Exit,
+        (0, 0, 0xF, 0xE) => Instruction::LowRes,
+        (0, 0, 0xF, 0xF) => Instruction::HighRes,
+        (0, 0, 0xE, 0xE) => Instruction::Return,
+        (1, _, _, _) => Instruction::Jump(nnn),
+        (2, _, _, _) => Instruction::Call(nnn),
+        (3, _, _, _) => Instruction::SkipIfEqual { x, nn },
+        (4, _, _, _) => Instruction::SkipIfNotEqual { x, nn },
+        (5, _, _, 0) => Instruction::SkipIfRegsEqual { x, y },
+        (6, _, _, _) => Instruction::Set { x, nn },
+        (7, _, _, _) => Instruction::AddValue { x, nn },
+        (8, _, _, 0) => Instruction::Copy { x, y },
+        (8, _, _, 1) => Instruction::Or { x, y },
+        (8, _, _, 2) => Instruction::And { x, y },
+        (8, _, _, 3) => Instruction::Xor { x, y },
+        (8, _, _, 4) => Instruction::Add { x, y },
+        (8, _, _, 5) => Instruction::Sub { x, y },
+        (8, _, _, 6) => Instruction::ShiftRight { x, y },
+        (8, _, _, 7) => Instruction::SubReverse { x, y },
+        (8, _, _, 0xE) => Instruction::ShiftLeft { x, y },
+        (9, _, _, 0) => Instruction::SkipIfRegsNotEqual { x, y },
+        (0xA, _, _, _) => Instruction::SetI(nnn),
+        (0xB, _, _, _) => Instruction::JumpOffset { x, nnn },
+        (0xC, _, _, _) => Instruction::Random { x, nn },
+        (0xD, _, _, _) => Instruction::Draw {
+            x,
+            y,
+            n: digit4 as u8,
+        },
+        (0xE, _, 9, 0xE) => Instruction::SkipIfKey(x),
+        (0xE, _, 0xA, 1) => Instruction::SkipIfNotKey(x),
+        (0xF, 0, 0, 0) => Instruction::SetILong,
+        (0xF, 0, 0, 2) => Instruction::LoadAudio,
+        (0xF, _, 0, 1) => Instruction::SelectPlanes(digit2 as u8),
+        (0xF, _, 0, 7) => Instruction::GetDelay(x),
+        (0xF, _, 0, 0xA) => Instruction::WaitKey(x),
+        (0xF, _, 1, 5) => Instruction::SetDelay(x),
+        (0xF, _, 1, 8) => Instruction::SetSound(x),
+        (0xF, _, 1, 0xE) => Instruction::AddI(x),
+        (0xF, _, 2, 9) => Instruction::SetIFont(x),
+        (0xF, _, 3, 0xA) => Instruction::SetPitch(x),
+        (0xF, _, 3, 3) => Instruction::StoreBcd(x),
+        (0xF, _, 5, 5) => Instruction::StoreRegs(x),
+        (0xF, _, 6, 5) => Instruction::LoadRegs(x),
+        (0xF, _, 7, 5) => Instruction::StoreFlags(x),
+        (0xF, _, 8, 5) => Instruction::LoadFlags(x),
+        (_, _, _, _) => return None,
+    };
+
+    Some(instruction)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::disasm::disassemble;
+    use crate::Emu;
+
+    #[test]
+    fn supported_opcodes() {
+        // Classic, SUPER-CHIP and XO-CHIP opcodes.
+        assert!(Emu::is_supported(0x8124));
+        assert!(Emu::is_supported(0x00FB));
+        assert!(Emu::is_supported(0xF002));
+        // 5XY1 isn't defined by any platform.
+        assert!(!Emu::is_supported(0x5121));
+        assert_eq!(disassemble(0x5121), "DW 0x5121");
+
+        let mut emu = Emu::new();
+        emu.load(&[0x51, 0x21]).unwrap();
+        assert_eq!(emu.explain_next(), "Unknown opcode 0x5121.");
+    }
+
+    #[test]
+    fn operands() {
+        assert_eq!(
+            decode(0xD12F),
+            Some(Instruction::Draw { x: 1, y: 2, n: 0xF })
+        );
+        assert_eq!(
+            decode(0xB3A5),
+            Some(Instruction::JumpOffset { x: 3, nnn: 0x3A5 })
+        );
+        assert_eq!(decode(0xF201), Some(Instruction::SelectPlanes(2)));
+        assert_eq!(decode(0x00C7), Some(Instruction::ScrollDown(7)));
+        assert_eq!(decode(0x8128), None);
+    }
+}
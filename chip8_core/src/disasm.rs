@@ -0,0 +1,253 @@
+// Assembly style mnemonics for debuggers, following the common Cowgod notation with the SUPER-CHIP and XO-CHIP
+// extensions. Opcodes are decoded by `decode`, the same as `execute`.
+
+#[cfg(feature = "serde")]
+use serde::Serialize;
+
+use crate::decode::{decode, Instruction};
+
+// Decode a single opcode, e.g. "LD V3, 0x2A". Opcodes the core doesn't handle come out as raw data words.
+// Quirk dependent opcodes are shown in their default form, and F000 doesn't show its address since that's
+// the following word.
+pub fn disassemble(op: u16) -> String {
+    let Some(instruction) = decode(op) else {
+        return format!("DW 0x{:04X}", op);
+    };
+
+    match instruction {
+        Instruction::Nop => "NOP".to_string(),
+        Instruction::Clear => "CLS".to_string(),
+        Instruction::ScrollDown(n) => format!("SCD {}", n),
+        Instruction::ScrollRight => "SCR".to_string(),
+        Instruction::ScrollLeft => "SCL".to_string(),
+        Instruction::Exit => "EXIT".to_string(),
+        Instruction::LowRes => "LOW".to_string(),
+        Instruction::HighRes => "HIGH".to_string(),
+        Instruction::Return => "RET".to_string(),
+        Instruction::Jump(nnn) => format!("JP 0x{:03X}", nnn),
+        Instruction::Call(nnn) => format!("CALL 0x{:03X}", nnn),
+        Instruction::SkipIfEqual { x, nn } => format!("SE V{:X}, 0x{:02X}", x, nn),
+        Instruction::SkipIfNotEqual { x, nn } => format!("SNE V{:X}, 0x{:02X}", x, nn),
+        Instruction::SkipIfRegsEqual { x, y } => format!("SE V{:X}, V{:X}", x, y),
+        Instruction::Set { x, nn } => format!("LD V{:X}, 0x{:02X}", x, nn),
+        Instruction::AddValue { x, nn } => format!("ADD V{:X}, 0x{:02X}", x, nn),
+        Instruction::Copy { x, y } => format!("LD V{:X}, V{:X}", x, y),
+        Instruction::Or { x, y } => format!("OR V{:X}, V{:X}", x, y),
+        Instruction::And { x, y } => format!("AND V{:X}, V{:X}", x, y),
+        Instruction::Xor { x, y } => format!("XOR V{:X}, V{:X}", x, y),
+        Instruction::Add { x, y } => format!("ADD V{:X}, V{:X}", x, y),
+        Instruction::Sub { x, y } => format!("SUB V{:X}, V{:X}", x, y),
+        Instruction::SubReverse { x, y } => format!("SUBN V{:X}, V{:X}", x, y),
+        Instruction::ShiftRight { x, y } => format!("SHR V{:X}, V{:X}", x, y),
+        Instruction::ShiftLeft { x, y } => format!("SHL V{:X}, V{:X}", x, y),
+        Instruction::SkipIfRegsNotEqual { x, y } => format!("SNE V{:X}, V{:X}", x, y),
+        Instruction::SetI(nnn) => format!("LD I, 0x{:03X}", nnn),
+        Instruction::JumpOffset { nnn, .. } => format!("JP V0, 0x{:03X}", nnn),
+        Instruction::Random { x, nn } => format!("RND V{:X}, 0x{:02X}", x, nn),
+        Instruction::Draw { x, y, n } => format!("DRW V{:X}, V{:X}, {}", x, y, n),
+        Instruction::SkipIfKey(x) => format!("SKP V{:X}", x),
+        Instruction::SkipIfNotKey(x) => format!("SKNP V{:X}", x),
+        Instruction::SetILong => "LD I, LONG".to_string(),
+        Instruction::LoadAudio => "AUDIO".to_string(),
+        Instruction::SelectPlanes(n) => format!("PLANE {}", n),
+        Instruction::GetDelay(x) => format!("LD V{:X}, DT", x),
+        Instruction::WaitKey(x) => format!("LD V{:X}, K", x),
+        Instruction::SetDelay(x) => format!("LD DT, V{:X}", x),
+        Instruction::SetSound(x) => format!("LD ST, V{:X}", x),
+        Instruction::AddI(x) => format!("ADD I, V{:X}", x),
+        Instruction::SetIFont(x) => format!("LD F, V{:X}", x),
+        Instruction::SetPitch(x) => format!("PITCH V{:X}", x),
+        Instruction::StoreBcd(x) => format!("LD B, V{:X}", x),
+        Instruction::StoreRegs(x) => format!("LD [I], V{:X}", x),
+        Instruction::LoadRegs(x) => format!("LD V{:X}, [I]", x),
+        Instruction::StoreFlags(x) => format!("LD R, V{:X}", x),
+        Instruction::LoadFlags(x) => format!("LD V{:X}, R", x),
+    }
+}
+
+// Decode every two-byte slot from start up to end, as (address, opcode, mnemonic), e.g. for a scrolling
+// disassembly view of a game starting at START_ADDR. Data mixed into the code is decoded like any other word.
+// Stops early rather than reading a partial word past the end of RAM.
+pub fn disassemble_range(ram: &[u8], start: u16, end: u16) -> Vec<(u16, u16, String)> {
+    let mut lines = Vec::new();
+    let mut addr = start as usize;
+
+    while addr < end as usize && addr + 1 < ram.len() {
+        let op = (ram[addr] as u16) << 8 | ram[addr + 1] as u16;
+        lines.push((addr as u16, op, disassemble(op)));
+        addr += 2;
+    }
+
+    lines
+}
+
+// One decoded instruction in a structured form for external tools. The mnemonic and operands are the parts of the
+// `disassemble` text, e.g. "LD V3, 0x2A" has the mnemonic "LD" and the operands "V3" and "0x2A".
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct DisasmEntry {
+    pub addr: u16,
+    pub opcode: u16,
+    pub mnemonic: String,
+    pub operands: Vec<String>,
+}
+
+impl DisasmEntry {
+    pub fn new(addr: u16, opcode: u16) -> Self {
+        let text = disassemble(opcode);
+        let (mnemonic, operands) = match text.split_once(' ') {
+            Some((mnemonic, operands)) => (mnemonic.to_string(), operands.split(", ").map(str::to_string).collect()),
+            None => (text, Vec::new()),
+        };
+
+        Self {
+            addr,
+            opcode,
+            mnemonic,
+            operands,
+        }
+    }
+}
+
+// Decode count two-byte slots starting at start, stopping early at the end of RAM like disassemble_range.
+pub fn disassemble_entries(ram: &[u8], start: u16, count: usize) -> Vec<DisasmEntry> {
+    let mut entries = Vec::new();
+    let mut addr = start as usize;
+
+    while entries.len() < count && addr + 1 < ram.len() {
+        let op = (ram[addr] as u16) << 8 | ram[addr + 1] as u16;
+        entries.push(DisasmEntry::new(addr as u16, op));
+        addr += 2;
+    }
+
+    entries
+}
+
+// Same as disassemble_entries as JSON lines, one object per instruction, e.g.
+// {"addr":512,"opcode":24578,"mnemonic":"LD","operands":["V0","0x02"]}
+#[cfg(feature = "serde")]
+pub fn disassemble_json(ram: &[u8], start: u16, count: usize) -> String {
+    disassemble_entries(ram, start, count)
+        .iter()
+        // Can't fail, the entries only hold numbers and strings.
+        .map(|entry| serde_json::to_string(entry).unwrap() + "\n")
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn one_opcode_per_family() {
+        let cases = [
+            (0x0000, "NOP"),
+            (0x00E0, "CLS"),
+            (0x00EE, "RET"),
+            (0x00C3, "SCD 3"),
+            (0x00FD, "EXIT"),
+            (0x1200, "JP 0x200"),
+            (0x2ABC, "CALL 0xABC"),
+            (0x312A, "SE V1, 0x2A"),
+            (0x412A, "SNE V1, 0x2A"),
+            (0x5120, "SE V1, V2"),
+            (0x632A, "LD V3, 0x2A"),
+            (0x7305, "ADD V3, 0x05"),
+            (0x8124, "ADD V1, V2"),
+            (0x8127, "SUBN V1, V2"),
+            (0x812E, "SHL V1, V2"),
+            (0x9120, "SNE V1, V2"),
+            (0xA123, "LD I, 0x123"),
+            (0xB123, "JP V0, 0x123"),
+            (0xC10F, "RND V1, 0x0F"),
+            (0xD015, "DRW V0, V1, 5"),
+            (0xE19E, "SKP V1"),
+            (0xE1A1, "SKNP V1"),
+            (0xF000, "LD I, LONG"),
+            (0xF107, "LD V1, DT"),
+            (0xF133, "LD B, V1"),
+            (0xF165, "LD V1, [I]"),
+            (0xF175, "LD R, V1"),
+            (0x5121, "DW 0x5121"),
+            (0xFFFF, "DW 0xFFFF"),
+        ];
+
+        for (op, mnemonic) in cases {
+            assert_eq!(disassemble(op), mnemonic, "0x{:04X}", op);
+        }
+    }
+
+    #[test]
+    fn range_of_a_small_program() {
+        // Loaded at 0x200: set V0 and I, draw, then loop forever.
+        let mut ram = vec![0; 0x200];
+        ram.extend([0x60, 0x05, 0xA0, 0x50, 0xD0, 0x05, 0x12, 0x06]);
+
+        assert_eq!(
+            disassemble_range(&ram, 0x200, 0x208),
+            [
+                (0x200, 0x6005, "LD V0, 0x05".to_string()),
+                (0x202, 0xA050, "LD I, 0x050".to_string()),
+                (0x204, 0xD005, "DRW V0, V0, 5".to_string()),
+                (0x206, 0x1206, "JP 0x206".to_string()),
+            ]
+        );
+
+        // A range ending mid-word still decodes the word it starts.
+        assert_eq!(disassemble_range(&ram, 0x204, 0x205).len(), 1);
+    }
+
+    #[test]
+    fn range_stops_before_a_partial_word() {
+        let ram = [0x00, 0xE0, 0x12, 0x00, 0xA3];
+
+        let lines = disassemble_range(&ram, 0, 0x10);
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[1], (2, 0x1200, "JP 0x200".to_string()));
+        assert!(disassemble_range(&ram, 4, 0x10).is_empty());
+    }
+
+    #[test]
+    fn structured_entries() {
+        let mut ram = vec![0; 0x200];
+        ram.extend([0x60, 0x02, 0x00, 0xE0, 0x51, 0x21]);
+
+        assert_eq!(
+            disassemble_entries(&ram, 0x200, 5),
+            [
+                DisasmEntry {
+                    addr: 0x200,
+                    opcode: 0x6002,
+                    mnemonic: "LD".to_string(),
+                    operands: vec!["V0".to_string(), "0x02".to_string()],
+                },
+                DisasmEntry {
+                    addr: 0x202,
+                    opcode: 0x00E0,
+                    mnemonic: "CLS".to_string(),
+                    operands: Vec::new(),
+                },
+                DisasmEntry {
+                    addr: 0x204,
+                    opcode: 0x5121,
+                    mnemonic: "DW".to_string(),
+                    operands: vec!["0x5121".to_string()],
+                },
+            ]
+        );
+        assert_eq!(disassemble_entries(&ram, 0x200, 1).len(), 1);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn json_lines() {
+        let mut ram = vec![0; 0x200];
+        ram.extend([0x60, 0x02, 0x00, 0xE0]);
+
+        assert_eq!(
+            disassemble_json(&ram, 0x200, 2),
+            "{\"addr\":512,\"opcode\":24578,\"mnemonic\":\"LD\",\"operands\":[\"V0\",\"0x02\"]}\n\
+             {\"addr\":514,\"opcode\":224,\"mnemonic\":\"CLS\",\"operands\":[]}\n"
+        );
+    }
+}
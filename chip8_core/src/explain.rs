@@ -0,0 +1,339 @@
+use crate::decode::{decode, Instruction};
+use crate::{Emu, XO_RAM_SIZE};
+
+impl Emu {
+    // Describe the instruction at the PC in plain English using the current register values,
+    // e.g. "Add V2 (=0x05) to V3 (=0x10), result 0x15 into V3, carry 0 into VF."
+    // Meant for educational step-through, nothing is executed.
+    pub fn explain_next(&self) -> String {
+        let pc = self.pc as usize;
+        let higher_byte = self.ram[pc % self.ram.len()] as u16;
+        let lower_byte = self.ram[(pc + 1) % self.ram.len()] as u16;
+        let op = (higher_byte << 8) | lower_byte;
+
+        let Some(instruction) = decode(op) else {
+            return unknown(op);
+        };
+
+        match instruction {
+            Instruction::Nop => "Do nothing.".to_string(),
+
+            Instruction::Clear => "Clear the selected planes of the screen.".to_string(),
+
+            Instruction::ScrollDown(n) => format!("Scroll the screen down by {} pixels.", n),
+
+            Instruction::ScrollRight => "Scroll the screen right by 4 pixels.".to_string(),
+
+            Instruction::ScrollLeft => "Scroll the screen left by 4 pixels.".to_string(),
+
+            Instruction::Exit => "Exit the interpreter, nothing runs until the game is reset.".to_string(),
+            Instruction::LowRes => "Switch to 64x32 low resolution and clear the screen.".to_string(),
+
+            Instruction::HighRes => "Switch to 128x64 high resolution and clear the screen.".to_string(),
+
+            Instruction::Return => match (self.sp as usize).checked_sub(1) {
+                Some(top) => format!("Return from subroutine to 0x{:03X}.", self.stack[top]),
+                None => "Return from subroutine, but the stack is empty.".to_string(),
+            },
+
+            Instruction::Jump(nnn) => format!("Jump to 0x{:03X}.", nnn),
+
+            Instruction::Call(nnn) => format!(
+                "Call subroutine at 0x{:03X}, returning to 0x{:03X}.",
+                nnn,
+                self.pc.wrapping_add(2)
+            ),
+
+            Instruction::SkipIfEqual { x, nn } => format!(
+                "Skip the next instruction if V{:X} (=0x{:02X}) equals 0x{:02X}: {}.",
+                x,
+                self.v_reg[x],
+                nn,
+                outcome(self.v_reg[x] == nn)
+            ),
+
+            Instruction::SkipIfNotEqual { x, nn } => format!(
+                "Skip the next instruction if V{:X} (=0x{:02X}) doesn't equal 0x{:02X}: {}.",
+                x,
+                self.v_reg[x],
+                nn,
+                outcome(self.v_reg[x] != nn)
+            ),
+
+            Instruction::SkipIfRegsEqual { x, y } => format!(
+                "Skip the next instruction if V{:X} (=0x{:02X}) equals V{:X} (=0x{:02X}): {}.",
+                x,
+                self.v_reg[x],
+                y,
+                self.v_reg[y],
+                outcome(self.v_reg[x] == self.v_reg[y])
+            ),
+
+            Instruction::Set { x, nn } => format!("Set V{:X} to 0x{:02X}.", x, nn),
+
+            Instruction::AddValue { x, nn } => format!(
+                "Add 0x{:02X} to V{:X} (=0x{:02X}), result 0x{:02X} into V{:X}.",
+                nn,
+                x,
+                self.v_reg[x],
+                self.v_reg[x].wrapping_add(nn),
+                x
+            ),
+
+            Instruction::Copy { x, y } => format!("Set V{:X} to V{:X} (=0x{:02X}).", x, y, self.v_reg[y]),
+
+            Instruction::Or { x, y } => format!(
+                "OR V{:X} (=0x{:02X}) with V{:X} (=0x{:02X}), result 0x{:02X} into V{:X}.",
+                x,
+                self.v_reg[x],
+                y,
+                self.v_reg[y],
+                self.v_reg[x] | self.v_reg[y],
+                x
+            ),
+
+            Instruction::And { x, y } => format!(
+                "AND V{:X} (=0x{:02X}) with V{:X} (=0x{:02X}), result 0x{:02X} into V{:X}.",
+                x,
+                self.v_reg[x],
+                y,
+                self.v_reg[y],
+                self.v_reg[x] & self.v_reg[y],
+                x
+            ),
+
+            Instruction::Xor { x, y } => format!(
+                "XOR V{:X} (=0x{:02X}) with V{:X} (=0x{:02X}), result 0x{:02X} into V{:X}.",
+                x,
+                self.v_reg[x],
+                y,
+                self.v_reg[y],
+                self.v_reg[x] ^ self.v_reg[y],
+                x
+            ),
+
+            Instruction::Add { x, y } => {
+                let (result, carry) = self.v_reg[x].overflowing_add(self.v_reg[y]);
+
+                format!(
+                    "Add V{:X} (=0x{:02X}) to V{:X} (=0x{:02X}), result 0x{:02X} into V{:X}, carry {} into VF.",
+                    y, self.v_reg[y], x, self.v_reg[x], result, x, carry as u8
+                )
+            }
+
+            Instruction::Sub { x, y } => {
+                let (result, borrow) = self.v_reg[x].overflowing_sub(self.v_reg[y]);
+
+                format!(
+                    "Subtract V{:X} (=0x{:02X}) from V{:X} (=0x{:02X}), result 0x{:02X} into V{:X}, no borrow {} into VF.",
+                    y, self.v_reg[y], x, self.v_reg[x], result, x, !borrow as u8
+                )
+            }
+
+            Instruction::SubReverse { x, y } => {
+                let (result, borrow) = self.v_reg[y].overflowing_sub(self.v_reg[x]);
+
+                format!(
+                    "Subtract V{:X} (=0x{:02X}) from V{:X} (=0x{:02X}), result 0x{:02X} into V{:X}, no borrow {} into VF.",
+                    x, self.v_reg[x], y, self.v_reg[y], result, x, !borrow as u8
+                )
+            }
+
+            Instruction::ShiftRight { x, y } => {
+                let (source, value) = self.explain_shift_source(x, y);
+
+                format!(
+                    "Shift V{:X} (=0x{:02X}) right by one, result 0x{:02X} into V{:X}, dropped bit {} into VF.",
+                    source,
+                    value,
+                    value >> 1,
+                    x,
+                    value & 1
+                )
+            }
+
+            Instruction::ShiftLeft { x, y } => {
+                let (source, value) = self.explain_shift_source(x, y);
+
+                format!(
+                    "Shift V{:X} (=0x{:02X}) left by one, result 0x{:02X} into V{:X}, dropped bit {} into VF.",
+                    source,
+                    value,
+                    value << 1,
+                    x,
+                    value >> 7
+                )
+            }
+
+            Instruction::SkipIfRegsNotEqual { x, y } => format!(
+                "Skip the next instruction if V{:X} (=0x{:02X}) doesn't equal V{:X} (=0x{:02X}): {}.",
+                x,
+                self.v_reg[x],
+                y,
+                self.v_reg[y],
+                outcome(self.v_reg[x] != self.v_reg[y])
+            ),
+
+            Instruction::SetI(nnn) => format!("Set I to 0x{:03X}.", nnn),
+
+            Instruction::JumpOffset { x, nnn } if self.quirks.jump_uses_vx => format!(
+                "Jump to V{:X} (=0x{:02X}) + 0x{:02X}, address 0x{:03X}.",
+                x,
+                self.v_reg[x],
+                nnn & 0xFF,
+                self.v_reg[x] as u16 + (nnn & 0xFF)
+            ),
+
+            Instruction::JumpOffset { nnn, .. } => format!(
+                "Jump to V0 (=0x{:02X}) + 0x{:03X}, address 0x{:03X}.",
+                self.v_reg[0],
+                nnn,
+                self.v_reg[0] as u16 + nnn
+            ),
+
+            Instruction::Random { x, nn } => format!("Set V{:X} to a random number AND 0x{:02X}.", x, nn),
+
+            Instruction::Draw { x, y, n: 0 } if self.quirks.large_sprites => format!(
+                "Draw a 16x16 sprite from I (=0x{:03X}) at V{:X} (={}), V{:X} (={}), setting VF on collision.",
+                self.i_reg, x, self.v_reg[x], y, self.v_reg[y]
+            ),
+
+            Instruction::Draw { x, y, n } => format!(
+                "Draw {} rows of sprite data from I (=0x{:03X}) at V{:X} (={}), V{:X} (={}), setting VF on collision.",
+                n, self.i_reg, x, self.v_reg[x], y, self.v_reg[y]
+            ),
+
+            Instruction::SkipIfKey(x) => format!(
+                "Skip the next instruction if key {:X} from V{:X} is pressed: {}.",
+                self.v_reg[x],
+                x,
+                outcome(self.keys[self.v_reg[x] as usize % self.keys.len()])
+            ),
+
+            Instruction::SkipIfNotKey(x) => format!(
+                "Skip the next instruction if key {:X} from V{:X} is not pressed: {}.",
+                self.v_reg[x],
+                x,
+                outcome(!self.keys[self.v_reg[x] as usize % self.keys.len()])
+            ),
+
+            Instruction::SetILong if self.ram.len() == XO_RAM_SIZE => {
+                let addr = self.ram[(pc + 2) % self.ram.len()] as u16;
+                let addr = (addr << 8) | self.ram[(pc + 3) % self.ram.len()] as u16;
+
+                format!("Set I to 0x{:04X} from the following word.", addr)
+            }
+
+            Instruction::LoadAudio => format!("Load the 16 byte audio pattern from I (=0x{:03X}).", self.i_reg),
+
+            Instruction::SelectPlanes(n) => {
+                format!("Select plane mask {} for drawing, clearing and scrolling.", n & 0b11)
+            }
+
+            Instruction::GetDelay(x) => format!("Set V{:X} to the delay timer (=0x{:02X}).", x, self.dt),
+
+            Instruction::WaitKey(x) => format!("Wait for a key press and store the key in V{:X}.", x),
+
+            Instruction::SetDelay(x) => format!("Set the delay timer to V{:X} (=0x{:02X}).", x, self.v_reg[x]),
+
+            Instruction::SetSound(x) => format!("Set the sound timer to V{:X} (=0x{:02X}).", x, self.v_reg[x]),
+
+            Instruction::AddI(x) => format!(
+                "Add V{:X} (=0x{:02X}) to I (=0x{:03X}), result 0x{:03X} into I.",
+                x,
+                self.v_reg[x],
+                self.i_reg,
+                self.i_reg.wrapping_add(self.v_reg[x] as u16)
+            ),
+
+            Instruction::SetIFont(x) => format!(
+                "Point I at the font sprite for digit {:X} from V{:X}.",
+                self.v_reg[x], x
+            ),
+
+            Instruction::SetPitch(x) => format!("Set the audio pitch to V{:X} (={}).", x, self.v_reg[x]),
+
+            Instruction::StoreBcd(x) => format!(
+                "Store the decimal digits of V{:X} (={}) at I (=0x{:03X}) through I+2.",
+                x, self.v_reg[x], self.i_reg
+            ),
+
+            Instruction::StoreRegs(x) => format!(
+                "Store V0 through V{:X} in RAM starting at I (=0x{:03X}){}.",
+                x,
+                self.i_reg,
+                self.explain_i_increment(x)
+            ),
+
+            Instruction::LoadRegs(x) => format!(
+                "Load V0 through V{:X} from RAM starting at I (=0x{:03X}){}.",
+                x,
+                self.i_reg,
+                self.explain_i_increment(x)
+            ),
+
+            Instruction::StoreFlags(x) => format!(
+                "Store V0 through V{:X} in the RPL user flags.",
+                x.min(7)
+            ),
+
+            Instruction::LoadFlags(x) => format!(
+                "Load V0 through V{:X} from the RPL user flags.",
+                x.min(7)
+            ),
+
+            Instruction::SetILong => unknown(op),
+        }
+    }
+
+    // Describe the I increment after FX55/FX65, depending on the quirk.
+    fn explain_i_increment(&self, x: usize) -> String {
+        if self.quirks.load_store_increments_i {
+            format!(", then advance I to 0x{:03X}", self.i_reg.wrapping_add(x as u16 + 1))
+        } else {
+            String::new()
+        }
+    }
+
+    // Register shifted by 8XY6/8XYE and its value, depending on the quirk.
+    fn explain_shift_source(&self, x: usize, y: usize) -> (usize, u8) {
+        let source = if self.quirks.shift_uses_vy { y } else { x };
+
+        (source, self.v_reg[source])
+    }
+}
+
+fn outcome(skip: bool) -> &'static str {
+    if skip {
+        "it will skip"
+    } else {
+        "it won't skip"
+    }
+}
+
+fn unknown(op: u16) -> String {
+    format!("Unknown opcode 0x{:04X}.", op)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn explain_add_with_carry() {
+        let mut emu = Emu::new();
+        emu.load(&[0x83, 0x24]).unwrap();
+        emu.v_reg[2] = 0x05;
+        emu.v_reg[3] = 0x10;
+        assert_eq!(
+            emu.explain_next(),
+            "Add V2 (=0x05) to V3 (=0x10), result 0x15 into V3, carry 0 into VF."
+        );
+
+        emu.v_reg[3] = 0xFF;
+        assert_eq!(
+            emu.explain_next(),
+            "Add V2 (=0x05) to V3 (=0xFF), result 0x04 into V3, carry 1 into VF."
+        );
+    }
+}
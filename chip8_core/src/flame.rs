@@ -0,0 +1,90 @@
+// Folded stack export for flame graphs, built from the trace hook.
+//
+// Every executed instruction counts as one cycle for the call stack it ran in. The stack follows 2NNN and 00EE,
+// each frame is named after the address of the subroutine that was called and the bottom frame is always `main`.
+// The output has one line per distinct stack, frames separated by `;` and followed by the cycle count:
+//
+//     main 812
+//     main;0x2A4 1520
+//     main;0x2A4;0x310 96
+//
+// This is the format `flamegraph.pl` and `inferno-flamegraph` read, e.g.
+// `inferno-flamegraph < game.folded > game.svg`.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::{Arc, Mutex};
+
+#[derive(Clone, Debug, Default)]
+pub struct FlameGraph {
+    // Addresses of the subroutines currently being executed, innermost last.
+    stack: Vec<u16>,
+    counts: BTreeMap<Vec<u16>, u64>,
+}
+
+impl FlameGraph {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Count one executed instruction, takes the same arguments as the trace hook.
+    // A call is counted in the caller and a return in the subroutine it leaves.
+    pub fn record(&mut self, _pc: u16, op: u16) {
+        *self.counts.entry(self.stack.clone()).or_insert(0) += 1;
+
+        if op & 0xF000 == 0x2000 {
+            self.stack.push(op & 0x0FFF);
+        } else if op == 0x00EE {
+            self.stack.pop();
+        }
+    }
+
+    // Create a trace hook recording into a shared graph, install it with `Emu::set_trace_hook`.
+    pub fn hook(graph: Arc<Mutex<Self>>) -> Box<dyn FnMut(u16, u16) + Send> {
+        Box::new(move |pc, op| graph.lock().unwrap().record(pc, op))
+    }
+
+    // Cycle count of each recorded stack, outermost frame first.
+    pub fn counts(&self) -> &BTreeMap<Vec<u16>, u64> {
+        &self.counts
+    }
+
+    // Render the counts in the folded format described at the top of this file.
+    pub fn folded(&self) -> String {
+        let mut out = String::new();
+        for (stack, count) in &self.counts {
+            out.push_str("main");
+            for addr in stack {
+                write!(out, ";{:#05X}", addr).unwrap();
+            }
+            writeln!(out, " {}", count).unwrap();
+        }
+
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Emu;
+
+    #[test]
+    fn subroutine_calls_produce_stack_frames() {
+        // Call 0x206 and spin at 0x202, the subroutine sets V0 and returns.
+        let program = [0x22, 0x06, 0x12, 0x02, 0x00, 0x00, 0x60, 0x01, 0x00, 0xEE];
+        let mut emu = Emu::with_seed(0);
+        emu.load(&program).unwrap();
+        let graph = Arc::new(Mutex::new(FlameGraph::new()));
+        emu.set_trace_hook(FlameGraph::hook(graph.clone()));
+
+        for _ in 0..5 {
+            emu.step().unwrap();
+        }
+
+        let graph = graph.lock().unwrap();
+        assert_eq!(graph.counts()[&vec![]], 3);
+        assert_eq!(graph.counts()[&vec![0x206]], 2);
+        assert_eq!(graph.folded(), "main 3\nmain;0x206 2\n");
+    }
+}
@@ -1,14 +1,55 @@
-use rand::random;
+pub mod compat;
+pub mod decode;
+pub mod disasm;
+pub mod flame;
+mod explain;
+pub mod replay;
+pub mod rewind;
+pub mod state;
+pub mod threaded;
+pub mod timing;
+pub mod tooling;
+
+use std::collections::{HashSet, VecDeque};
+use std::fmt;
+use std::time::Duration;
+
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha12Rng;
+use decode::{decode, Instruction};
+use timing::Pacer;
 
 pub const SCREEN_WIDTH: usize = 64;
 pub const SCREEN_HEIGHT: usize = 32;
-
-const RAM_SIZE: usize = 4096;
+// SUPER-CHIP high resolution mode.
+pub const HIRES_SCREEN_WIDTH: usize = 128;
+pub const HIRES_SCREEN_HEIGHT: usize = 64;
+// XO-CHIP bit-planes, each pixel holds one bit per plane giving a color index from 0 to 3.
+const NUM_PLANES: usize = 2;
+// The screen buffer is sized for the largest resolution, one bit per pixel in a u128 for each row and plane.
+const BLANK_SCREEN: [[u128; NUM_PLANES]; HIRES_SCREEN_HEIGHT] = [[0; NUM_PLANES]; HIRES_SCREEN_HEIGHT];
+
+pub const RAM_SIZE: usize = 4096;
+// XO-CHIP extended memory, addressable through F000 NNNN.
+pub const XO_RAM_SIZE: usize = 0x10000;
 const NUM_REGS: usize = 16;
 const STACK_SIZE: usize = 16;
-const NUM_KEYS: usize = 16;
+pub const NUM_KEYS: usize = 16;
+// SUPER-CHIP's RPL user flags saved and restored by FX75/FX85.
+const NUM_RPL_FLAGS: usize = 8;
+// XO-CHIP audio pattern, 128 one bit samples.
+pub const AUDIO_BUFFER_SIZE: usize = 16;
+// Pitch giving a playback rate of 4000Hz.
+const DEFAULT_PITCH: u8 = 64;
+// Default instruction rate, 10 instructions per 60Hz frame.
+const CLOCK_HZ: u32 = 600;
+const TIMER_HZ: u32 = 60;
 // Game code on Chip-8 always starts on this memory address.
-const START_ADDR: u16 = 0x200;
+pub const START_ADDR: u16 = 0x200;
+// Largest game that fits in standard RAM after the start address.
+pub const MAX_ROM_SIZE: usize = RAM_SIZE - START_ADDR as usize;
+// Most frames kept by the ASCII frame log, older frames are dropped first.
+const FRAME_LOG_SIZE: usize = 600;
 const FONTSET_SIZE: usize = 80;
 const FONTSET: [u8; FONTSET_SIZE] = [
     0xF0, 0x90, 0x90, 0x90, 0xF0, // 0
@@ -31,30 +72,372 @@ const FONTSET: [u8; FONTSET_SIZE] = [
 
 pub struct Emu {
     pc: u16,
-    ram: [u8; RAM_SIZE],
-    screen: [bool; SCREEN_WIDTH * SCREEN_HEIGHT],
+    // RAM_SIZE bytes, or XO_RAM_SIZE with extended memory enabled on the builder.
+    ram: Vec<u8>,
+    // Rows of the active resolution from the top, with bit x of each plane holding the pixel in column x.
+    screen: [[u128; NUM_PLANES]; HIRES_SCREEN_HEIGHT],
+    // Bit mask of the planes drawn to and cleared, selected by FN01.
+    planes: u8,
+    // SUPER-CHIP 128x64 mode, toggled by 00FF and 00FE.
+    hires: bool,
     v_reg: [u8; NUM_REGS],
+    // Written by FX75 and read by FX85, games use them to keep e.g. high scores. Cleared on reset like the rest of
+    // the machine, unlike the calculator's flags which survived between games.
+    rpl_flags: [u8; NUM_RPL_FLAGS],
     i_reg: u16,
     sp: u16,
-    stack: [u16; STACK_SIZE],
+    stack: Vec<u16>,
     keys: [bool; NUM_KEYS],
     dt: u8,
     st: u8,
+    // XO-CHIP sound, loaded by F002 and FX3A.
+    audio_buffer: [u8; AUDIO_BUFFER_SIZE],
+    pitch: u8,
+    // Set whenever the screen buffer is modified.
+    display_changed: bool,
+    // Same as display_changed but cleared separately, so a frontend can skip redraws while something else takes
+    // the changes, e.g. for capturing frames.
+    display_dirty: bool,
+    // Set by a draw and cleared on the next timer tick.
+    vblank_pending: bool,
+    // Number of instructions executed since construction or reset.
+    cycles: u64,
+    // Most instructions a single tick_n call may run.
+    instruction_limit: Option<usize>,
+    unknown_opcodes: UnknownOpcodes,
+    // Unknown opcodes skipped since construction or reset.
+    unknown_opcode_count: u64,
+    // Instructions executed per top nibble since construction or reset.
+    #[cfg(feature = "profile")]
+    opcode_histogram: [u64; 16],
+    // Sound timer on/off transitions as (cycle, beeping), only recorded when enabled.
+    audio_log: Option<Vec<(u64, bool)>>,
+    // Sound timer values below this don't count as beeping.
+    beep_threshold: u8,
+    // ASCII renderings of the screen, only recorded when enabled.
+    frame_log: Option<FrameLog>,
+    // Debugging aid, when set 00E0 leaves the screen untouched.
+    ignore_clears: bool,
+    // Clears suppressed by ignore_clears since construction or reset.
+    ignored_clears: u64,
+    // Value unused RAM is filled with.
+    ram_fill: u8,
+    // When set, instruction writes into the fontset are ignored.
+    protect_font: bool,
+    // Convert elapsed time into instructions and timer ticks for `advance`.
+    clock: Pacer,
+    timer_clock: Pacer,
+    quirks: Quirks,
+    // Whether the sound timer was active after the previous timer tick, used to report edges.
+    beeping_last_tick: bool,
+    // Source for CXNN, seeded from entropy unless created with `with_seed`. The same generator as rand's StdRng,
+    // used directly since its position can be saved and restored.
+    rng: ChaCha12Rng,
+    // Called with the PC and opcode of every instruction before it executes.
+    trace_hook: Option<Box<dyn FnMut(u16, u16) + Send>>,
+    // Addresses tick_n stops at before fetching.
+    breakpoints: HashSet<u16>,
+    // Breakpoint the last tick_n stopped at, so the next call runs past it instead of stopping again.
+    break_pc: Option<u16>,
+    // Addresses whose writes are reported by tick.
+    watchpoints: HashSet<u16>,
+    // First watched write made by the current instruction.
+    watch_hit: Option<StepResult>,
+    // Set by 00FD, nothing runs until reset.
+    halted: bool,
 }
 
-impl Emu {
+// Render a screen buffer as rows of '#' for lit and ' ' for unlit pixels, in any plane.
+fn ascii_frame(screen: &[u8], width: usize) -> String {
+    let mut frame = String::with_capacity(screen.len() + screen.len() / width);
+
+    for row in screen.chunks(width) {
+        frame.extend(row.iter().map(|&pixel| if pixel != 0 { '#' } else { ' ' }));
+        frame.push('\n');
+    }
+
+    frame
+}
+
+// Runs of lit pixels with the same color index in a screen buffer, as (row, start x, length), row by row.
+// Lets frontends draw a whole run with a single rectangle, e.g. for buffers blended from several frames.
+pub fn display_spans(screen: &[u8], width: usize) -> Vec<(usize, usize, usize)> {
+    let mut spans = Vec::new();
+
+    for (y, row) in screen.chunks(width).enumerate() {
+        let mut x = 0;
+
+        while x < row.len() {
+            let len = row[x..].iter().take_while(|&&pixel| pixel == row[x]).count();
+
+            if row[x] != 0 {
+                spans.push((y, x, len));
+            }
+
+            x += len;
+        }
+    }
+
+    spans
+}
+
+// Debugging aid recording a frame by frame textual movie of the display.
+struct FrameLog {
+    frames: VecDeque<String>,
+    // Last logged frame, kept separately so it survives the log being taken.
+    last: String,
+}
+
+// Sound edges seen by a tick_timers call, both are set for a beep lasting a single tick.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TimerEvent {
+    // The sound timer became active since the previous tick.
+    pub beep_started: bool,
+    // The sound timer ran out, or was cleared, since the previous tick.
+    pub beep_stopped: bool,
+}
+
+// How a batch of instructions ended, when it didn't fail.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StepResult {
+    // The whole instruction budget ran, or was cut short by the display-wait quirk.
+    Completed,
+    // The PC reached the breakpoint at this address, its instruction hasn't executed yet.
+    HitBreakpoint(u16),
+    // The last instruction wrote to a watched address, only the first such write is reported.
+    Watchpoint { addr: u16, old: u8, new: u8 },
+    // The game exited with 00FD, the PC stays on that instruction and nothing runs until reset.
+    Halted,
+}
+
+// Reasons a game can't be loaded.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LoadError {
+    // The game doesn't fit in RAM after the address it is loaded at.
+    TooLarge { len: usize, max: usize },
+    // The load address is past the end of RAM.
+    AddressOutOfRange(u16),
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LoadError::TooLarge { len, max } => {
+                write!(f, "game is too large: {} bytes, at most {} fit in memory", len, max)
+            }
+            LoadError::AddressOutOfRange(addr) => write!(f, "load address 0x{:04X} is past the end of memory", addr),
+        }
+    }
+}
+
+impl std::error::Error for LoadError {}
+
+// Reasons execution can't continue.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ExecError {
+    // The PC points past the last full instruction in RAM.
+    PcOutOfBounds(u16),
+    // A call was made with every stack entry in use.
+    StackOverflow,
+    // A return was made with an empty stack.
+    StackUnderflow,
+    // A tick_n call asked for more instructions than the limit allows and ran all of those.
+    InstructionLimit(usize),
+    // The opcode isn't recognized, only reported with UnknownOpcodes::Error. The PC is past it.
+    UnknownOpcode(u16),
+}
+
+impl fmt::Display for ExecError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ExecError::PcOutOfBounds(pc) => write!(f, "program counter 0x{:03X} is out of bounds", pc),
+            ExecError::StackOverflow => write!(f, "stack overflow"),
+            ExecError::StackUnderflow => write!(f, "stack underflow"),
+            ExecError::InstructionLimit(limit) => write!(f, "instruction limit of {} reached", limit),
+            ExecError::UnknownOpcode(op) => write!(f, "unknown opcode 0x{:04X}", op),
+        }
+    }
+}
+
+impl std::error::Error for ExecError {}
+
+// Behaviors that differ between Chip-8 interpreters. The defaults match this emulator's original behavior.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Quirks {
+    // 8XY6/8XYE shift VY and store the result in VX, instead of shifting VX in place.
+    pub shift_uses_vy: bool,
+    // FX55/FX65 leave I pointing past the last register, as on the COSMAC VIP.
+    pub load_store_increments_i: bool,
+    // DXY0 draws a 16x16 sprite from 32 bytes, two per row, as on SUPER-CHIP. Otherwise it draws nothing.
+    pub large_sprites: bool,
+    // DXYN waits for the next vertical blank as on the COSMAC VIP, so tick_n stops after a draw until tick_timers.
+    pub display_wait: bool,
+    // DXYN wraps sprites around the left and right screen edges. When unset they are clipped at the right edge
+    // instead, as SUPER-CHIP games expect, and only the starting position wraps.
+    pub wrap_x: bool,
+    // Same as wrap_x for the top and bottom edges.
+    pub wrap_y: bool,
+    // BNNN is read as BXNN and jumps to VX + NN, as on SUPER-CHIP, instead of V0 + NNN.
+    pub jump_uses_vx: bool,
+}
+
+impl Default for Quirks {
+    fn default() -> Self {
+        Self {
+            shift_uses_vy: false,
+            load_store_increments_i: false,
+            large_sprites: false,
+            display_wait: false,
+            wrap_x: true,
+            wrap_y: true,
+            jump_uses_vx: false,
+        }
+    }
+}
+
+// What to do with an opcode that isn't recognized, e.g. in a game for an unsupported platform or data jumped into.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum UnknownOpcodes {
+    // Panic, the strict behavior for development.
+    #[default]
+    Panic,
+    // Fail with ExecError::UnknownOpcode.
+    Error,
+    // Treat it as a no-op and count it, see `Emu::unknown_opcode_count`. The trace hook still sees it.
+    Skip,
+}
+
+// Construction-time configuration for an emulator, `Emu::new()` is the same as `EmuBuilder::new().build()`.
+// For example a SUPER-CHIP game with reproducible CXNN results:
+//
+//     let emu = EmuBuilder::new()
+//         .quirks(Quirks { large_sprites: true, wrap_x: false, wrap_y: false, ..Quirks::default() })
+//         .seed(42)
+//         .highres(true)
+//         .build();
+//
+// Or an XO-CHIP game that needs the larger memory:
+//
+//     let emu = EmuBuilder::new().extended_ram(true).build();
+//
+// Both examples are built by the builder_examples test, keep them in sync.
+pub struct EmuBuilder {
+    stack_size: usize,
+    ram_fill: u8,
+    clock_hz: u32,
+    extended_ram: bool,
+    quirks: Quirks,
+    seed: Option<u64>,
+    highres: bool,
+}
+
+impl EmuBuilder {
     pub fn new() -> Self {
-        let mut emu = Self {
+        Self {
+            stack_size: STACK_SIZE,
+            ram_fill: 0,
+            clock_hz: CLOCK_HZ,
+            extended_ram: false,
+            quirks: Quirks::default(),
+            seed: None,
+            highres: false,
+        }
+    }
+
+    // Set the number of stack entries, some extended interpreters allow deeper call stacks.
+    pub fn stack_size(mut self, stack_size: usize) -> Self {
+        self.stack_size = stack_size;
+        self
+    }
+
+    // Fill RAM outside the fontset with the given byte on construction and reset, defaults to 0.
+    // A recognizable value like 0xFF makes jumps into uninitialized memory obvious instead of silent 0000 no-ops.
+    pub fn fill_ram(mut self, value: u8) -> Self {
+        self.ram_fill = value;
+        self
+    }
+
+    // Set the instruction rate used by `Emu::advance`, defaults to 600Hz.
+    pub fn clock_hz(mut self, clock_hz: u32) -> Self {
+        self.clock_hz = clock_hz;
+        self
+    }
+
+    // Use XO-CHIP's 64KB of RAM instead of the standard 4KB, allowing larger games and F000 NNNN addresses.
+    pub fn extended_ram(mut self, enabled: bool) -> Self {
+        self.extended_ram = enabled;
+        self
+    }
+
+    // Set the interpreter quirks, they can still be changed later with `Emu::set_quirks`.
+    pub fn quirks(mut self, quirks: Quirks) -> Self {
+        self.quirks = quirks;
+        self
+    }
+
+    // Seed the CXNN random number generator, see `Emu::with_seed`. Seeded from entropy otherwise.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    // Start in SUPER-CHIP's 128x64 high resolution instead of 64x32. Games can still switch with 00FE and 00FF,
+    // and a reset returns to low resolution.
+    pub fn highres(mut self, enabled: bool) -> Self {
+        self.highres = enabled;
+        self
+    }
+
+    pub fn build(self) -> Emu {
+        let ram_size = if self.extended_ram { XO_RAM_SIZE } else { RAM_SIZE };
+        let mut emu = Emu {
             pc: START_ADDR,
-            ram: [0; RAM_SIZE],
-            screen: [false; SCREEN_WIDTH * SCREEN_HEIGHT],
+            ram: vec![self.ram_fill; ram_size],
+            screen: BLANK_SCREEN,
+            planes: 1,
+            hires: self.highres,
             v_reg: [0; NUM_REGS],
+            rpl_flags: [0; NUM_RPL_FLAGS],
             i_reg: 0,
             sp: 0,
-            stack: [0; STACK_SIZE],
+            stack: vec![0; self.stack_size],
             keys: [false; NUM_KEYS],
             dt: 0,
             st: 0,
+            audio_buffer: [0; AUDIO_BUFFER_SIZE],
+            pitch: DEFAULT_PITCH,
+            display_changed: false,
+            // Dirty from the start so the first frame is drawn.
+            display_dirty: true,
+            vblank_pending: false,
+            cycles: 0,
+            instruction_limit: None,
+            unknown_opcodes: UnknownOpcodes::default(),
+            unknown_opcode_count: 0,
+            #[cfg(feature = "profile")]
+            opcode_histogram: [0; 16],
+            audio_log: None,
+            beep_threshold: 0,
+            frame_log: None,
+            ignore_clears: false,
+            ignored_clears: 0,
+            ram_fill: self.ram_fill,
+            protect_font: false,
+            clock: Pacer::new(self.clock_hz),
+            timer_clock: Pacer::new(TIMER_HZ),
+            quirks: self.quirks,
+            beeping_last_tick: false,
+            rng: match self.seed {
+                Some(seed) => ChaCha12Rng::seed_from_u64(seed),
+                None => ChaCha12Rng::from_entropy(),
+            },
+            trace_hook: None,
+            breakpoints: HashSet::new(),
+            break_pc: None,
+            watchpoints: HashSet::new(),
+            watch_hit: None,
+            halted: false,
         };
 
         // Copy the fontset into RAM.
@@ -62,25 +445,320 @@ impl Emu {
 
         emu
     }
+}
+
+impl Default for EmuBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Emu {
+    pub fn new() -> Self {
+        EmuBuilder::new().build()
+    }
+
+    // Create an emulator whose CXNN results are fully determined by the seed, for tests and replays.
+    // The sequence continues across resets.
+    pub fn with_seed(seed: u64) -> Self {
+        EmuBuilder::new().seed(seed).build()
+    }
 
     // Reset the emulator to the default settings.
     pub fn reset(&mut self) {
         self.pc = START_ADDR;
-        self.ram = [0; RAM_SIZE];
-        self.screen = [false; SCREEN_WIDTH * SCREEN_HEIGHT];
+        self.ram.fill(self.ram_fill);
+        self.screen = BLANK_SCREEN;
+        self.planes = 1;
+        self.hires = false;
         self.v_reg = [0; NUM_REGS];
+        self.rpl_flags = [0; NUM_RPL_FLAGS];
         self.i_reg = 0;
         self.sp = 0;
-        self.stack = [0; STACK_SIZE];
+        self.stack.fill(0);
         self.keys = [false; NUM_KEYS];
         self.dt = 0;
         self.st = 0;
+        self.audio_buffer = [0; AUDIO_BUFFER_SIZE];
+        self.pitch = DEFAULT_PITCH;
+        self.mark_display_changed();
+        self.vblank_pending = false;
+        self.cycles = 0;
+        self.unknown_opcode_count = 0;
+        self.ignored_clears = 0;
+        #[cfg(feature = "profile")]
+        {
+            self.opcode_histogram = [0; 16];
+        }
+        self.break_pc = None;
+        self.halted = false;
+        if let Some(log) = &mut self.audio_log {
+            log.clear();
+        }
         self.ram[..FONTSET_SIZE].copy_from_slice(&FONTSET);
     }
 
-    // Return pointer to the screen array.
-    pub fn get_display(&self) -> &[bool] {
-        &self.screen
+    // Zero both timers without touching the rest of the state, e.g. to silence audio immediately.
+    pub fn reset_timers(&mut self) {
+        let was_beeping = self.is_beeping();
+        self.dt = 0;
+        self.st = 0;
+        self.log_audio(was_beeping);
+    }
+
+    // Both timers count down at 60Hz, driven by tick_timers.
+    pub fn get_delay_timer(&self) -> u8 {
+        self.dt
+    }
+
+    // A frontend can poll is_beeping instead to drive audio.
+    pub fn get_sound_timer(&self) -> u8 {
+        self.st
+    }
+
+    // Return the XO-CHIP audio pattern, played most significant bit first and looped while beeping.
+    pub fn get_audio_buffer(&self) -> &[u8; AUDIO_BUFFER_SIZE] {
+        &self.audio_buffer
+    }
+
+    // Return the XO-CHIP pitch, the pattern plays at 4000 * 2^((pitch - 64) / 48) bits per second.
+    pub fn get_audio_pitch(&self) -> u8 {
+        self.pitch
+    }
+
+    // Return whether the sound timer is active and at or above the beep threshold.
+    pub fn is_beeping(&self) -> bool {
+        self.st > 0 && self.st >= self.beep_threshold
+    }
+
+    // Set the minimum sound timer value that counts as beeping, to suppress short clicks.
+    // Defaults to 0, beeping whenever the sound timer is active. The beep edges reported by tick_timers and the
+    // audio log follow is_beeping, so they apply the threshold too.
+    pub fn set_beep_threshold(&mut self, threshold: u8) {
+        self.beep_threshold = threshold;
+    }
+
+    // Return whether either timer is nonzero, so a frontend can skip timer work while both are idle.
+    // Note that tick_timers also marks the end of a frame for needs_vblank and the frame log.
+    pub fn timers_active(&self) -> bool {
+        self.dt > 0 || self.st > 0
+    }
+
+    // Select the interpreter behaviors to emulate, they are kept across resets.
+    pub fn set_quirks(&mut self, quirks: Quirks) {
+        self.quirks = quirks;
+    }
+
+    // Return the screen for the active resolution, one pixel per byte and one row after another.
+    // Each pixel is a color index from 0 to 3, bit 0 set for the first plane and bit 1 for the second.
+    // Games that don't select planes only ever use 0 for unlit and 1 for lit.
+    pub fn get_display(&self) -> Vec<u8> {
+        let (width, height) = self.get_screen_size();
+
+        (0..height).flat_map(|y| (0..width).map(move |x| self.pixel(x, y))).collect()
+    }
+
+    // Runs of lit pixels on the display, see display_spans. The color of a run is the color of its first pixel.
+    pub fn get_display_spans(&self) -> Vec<(usize, usize, usize)> {
+        let (width, _) = self.get_screen_size();
+
+        display_spans(&self.get_display(), width)
+    }
+
+    // Color index of a single pixel in the active resolution, 0 for coordinates off the screen.
+    pub fn pixel(&self, x: usize, y: usize) -> u8 {
+        let (width, height) = self.get_screen_size();
+
+        if x >= width || y >= height {
+            return 0;
+        }
+
+        let mut color = 0;
+        for plane in 0..NUM_PLANES {
+            if self.screen[y][plane] & (1 << x) != 0 {
+                color |= 1 << plane;
+            }
+        }
+
+        color
+    }
+
+    // Render the display as rows of '#' for lit and ' ' for unlit pixels, each row ending in a newline.
+    // Deterministic given a seed, so headless runs of test ROMs can be compared against snapshots.
+    pub fn render_ascii(&self) -> String {
+        let (width, _) = self.get_screen_size();
+
+        ascii_frame(&self.get_display(), width)
+    }
+
+    // Width of the active resolution, 64 or 128 in SUPER-CHIP high resolution. Frontends should use this rather
+    // than SCREEN_WIDTH, which is only the low resolution width.
+    pub fn display_width(&self) -> usize {
+        self.get_screen_size().0
+    }
+
+    // Height of the active resolution, 32 or 64 in SUPER-CHIP high resolution.
+    pub fn display_height(&self) -> usize {
+        self.get_screen_size().1
+    }
+
+    // Return the active resolution as (width, height).
+    pub fn get_screen_size(&self) -> (usize, usize) {
+        if self.hires {
+            (HIRES_SCREEN_WIDTH, HIRES_SCREEN_HEIGHT)
+        } else {
+            (SCREEN_WIDTH, SCREEN_HEIGHT)
+        }
+    }
+
+    // Read-only access to the CPU state for debuggers.
+    pub fn get_registers(&self) -> &[u8; NUM_REGS] {
+        &self.v_reg
+    }
+
+    pub fn get_i(&self) -> u16 {
+        self.i_reg
+    }
+
+    pub fn get_pc(&self) -> u16 {
+        self.pc
+    }
+
+    pub fn get_sp(&self) -> u16 {
+        self.sp
+    }
+
+    // Return the whole stack, entries at and above the stack pointer are stale.
+    pub fn get_stack(&self) -> &[u16] {
+        &self.stack
+    }
+
+    pub fn get_ram(&self) -> &[u8] {
+        &self.ram
+    }
+
+    // Return whether the screen changed since the last call and reset the flag.
+    // Useful for capturing exactly one frame per display update.
+    pub fn take_display_changed(&mut self) -> bool {
+        std::mem::take(&mut self.display_changed)
+    }
+
+    // Return whether the screen needs redrawing since the last call and reset the flag, independent of
+    // take_display_changed. Frontends can skip drawing while nothing changed.
+    pub fn take_display_dirty(&mut self) -> bool {
+        std::mem::take(&mut self.display_dirty)
+    }
+
+    // Return whether a draw happened since the last timer tick.
+    // Frontends calling tick directly and emulating the display-wait quirk stop ticking until the next frame while
+    // this is set, tick_n already does so when the quirk is enabled.
+    pub fn needs_vblank(&self) -> bool {
+        self.vblank_pending
+    }
+
+    // Number of instructions executed since construction or the last reset.
+    pub fn cycles(&self) -> u64 {
+        self.cycles
+    }
+
+    // Cap the instructions a single tick_n call may run, kept across resets and off by default. A call asking for
+    // more runs up to the limit and then fails with ExecError::InstructionLimit, so tooling running until a halt or
+    // breakpoint with a huge count notices a game that never gets there, e.g. one stuck on a 1NNN jump to itself.
+    pub fn set_instruction_limit(&mut self, limit: Option<usize>) {
+        self.instruction_limit = limit;
+    }
+
+    // Choose how unknown opcodes are handled, kept across resets.
+    pub fn set_unknown_opcodes(&mut self, mode: UnknownOpcodes) {
+        self.unknown_opcodes = mode;
+    }
+
+    // Number of unknown opcodes skipped since construction or the last reset.
+    pub fn unknown_opcode_count(&self) -> u64 {
+        self.unknown_opcode_count
+    }
+
+    // Instructions executed since construction or the last reset, indexed by the opcode's top nibble, e.g. index 0xD
+    // counts draws. Only available with the `profile` feature.
+    #[cfg(feature = "profile")]
+    pub fn opcode_histogram(&self) -> &[u64; 16] {
+        &self.opcode_histogram
+    }
+
+    // Whether the game exited with 00FD, frontends can stop ticking or tell the player.
+    pub fn is_halted(&self) -> bool {
+        self.halted
+    }
+
+    // Start or stop recording sound timer transitions, useful for checking audio in headless runs.
+    pub fn set_audio_logging(&mut self, enabled: bool) {
+        self.audio_log = if enabled { Some(Vec::new()) } else { None };
+    }
+
+    // Return the recorded (cycle, beeping) transitions and clear the log.
+    pub fn take_audio_log(&mut self) -> Vec<(u64, bool)> {
+        self.audio_log.as_mut().map(std::mem::take).unwrap_or_default()
+    }
+
+    // Start or stop logging the screen as ASCII whenever it changes, checked once per timer tick.
+    // Only the last 600 frames are kept.
+    pub fn set_frame_logging(&mut self, enabled: bool) {
+        self.frame_log = enabled.then(|| FrameLog {
+            frames: VecDeque::new(),
+            last: String::new(),
+        });
+    }
+
+    // Return the logged frames, oldest first, and clear the log.
+    pub fn take_frame_log(&mut self) -> Vec<String> {
+        match &mut self.frame_log {
+            Some(log) => log.frames.drain(..).collect(),
+            None => Vec::new(),
+        }
+    }
+
+    // Debugging aid: ignore 00E0 so the cumulative drawing of a ROM stays visible.
+    // This changes emulation and will break most games, don't leave it on for normal play.
+    pub fn set_ignore_clears(&mut self, ignore: bool) {
+        self.ignore_clears = ignore;
+    }
+
+    // Number of 00E0 clears suppressed by set_ignore_clears since construction or the last reset.
+    pub fn ignored_clear_count(&self) -> u64 {
+        self.ignored_clears
+    }
+
+    // Write-protect the fontset so FX33 and FX55 can't corrupt it, writes into it are silently ignored.
+    // Off by default for compatibility.
+    pub fn set_font_protection(&mut self, protect: bool) {
+        self.protect_font = protect;
+    }
+
+    // Observe every executed instruction, the hook receives the PC it was fetched from and the opcode.
+    // Useful for logging, profiling and coverage, it is kept across resets. The hook has to be Send so the
+    // emulator can still be moved to another thread.
+    pub fn set_trace_hook(&mut self, hook: Box<dyn FnMut(u16, u16) + Send>) {
+        self.trace_hook = Some(hook);
+    }
+
+    // Make tick_n and advance stop before executing the instruction at the given address.
+    // Calling them again continues past it. Breakpoints are kept across resets.
+    pub fn set_breakpoint(&mut self, addr: u16) {
+        self.breakpoints.insert(addr);
+    }
+
+    pub fn clear_breakpoint(&mut self, addr: u16) {
+        self.breakpoints.remove(&addr);
+    }
+
+    // Make tick report instructions writing to the given address, tick_n and advance stop right after them.
+    // Writes blocked by font protection don't count. Watchpoints are kept across resets.
+    pub fn set_watchpoint(&mut self, addr: u16) {
+        self.watchpoints.insert(addr);
+    }
+
+    pub fn clear_watchpoint(&mut self, addr: u16) {
+        self.watchpoints.remove(&addr);
     }
 
     // Keypress handling.
@@ -88,176 +766,434 @@ impl Emu {
         self.keys[idx] = pressed;
     }
 
-    // Load game code from a file into RAM.
-    pub fn load(&mut self, data: &[u8]) {
-        let start = START_ADDR as usize;
-        let end = (START_ADDR as usize) + data.len();
+    // Set the state of all keys at once, e.g. to feed recorded input frames.
+    pub fn set_keys(&mut self, keys: [bool; NUM_KEYS]) {
+        self.keys = keys;
+    }
+
+    pub fn get_keys(&self) -> &[bool; NUM_KEYS] {
+        &self.keys
+    }
+
+    // Load game code from a file into RAM, failing if it doesn't fit.
+    // Up to MAX_ROM_SIZE bytes fit, more with extended memory.
+    pub fn load(&mut self, data: &[u8]) -> Result<(), LoadError> {
+        self.load_at(data, START_ADDR)
+    }
+
+    // Load game code at another address and start executing there, e.g. 0x600 for ETI-660 games.
+    pub fn load_at(&mut self, data: &[u8], addr: u16) -> Result<(), LoadError> {
+        if addr as usize > self.ram.len() {
+            return Err(LoadError::AddressOutOfRange(addr));
+        }
+
+        let max = self.ram.len() - addr as usize;
+
+        if data.len() > max {
+            return Err(LoadError::TooLarge { len: data.len(), max });
+        }
+
+        let start = addr as usize;
+        let end = start + data.len();
         self.ram[start..end].copy_from_slice(data);
+        self.pc = addr;
+
+        Ok(())
+    }
+
+    // Return whether the given opcode is recognized by this build. F000 NNNN is included, but it only runs on an
+    // emulator with extended memory and is handled as an unknown opcode otherwise.
+    pub fn is_supported(op: u16) -> bool {
+        decode(op).is_some()
     }
 
     // 1. Fetch the value from our game (loaded into RAM) at the memory address stored in our PC.
     // 2. Decode this instruction.
     // 3. Execute, which will possibly involve modifying our CPU registers or RAM.
     // 4. Move the PC to the next instruction and repeat.
-    pub fn tick(&mut self) {
-        let op = self.fetch();
-        self.execute(op);
+    // Returns StepResult::Watchpoint if the instruction wrote to a watched address, and StepResult::Halted without
+    // executing anything once the game exited.
+    pub fn tick(&mut self) -> Result<StepResult, ExecError> {
+        if !self.halted {
+            self.step()?;
+        }
+
+        if self.halted {
+            return Ok(StepResult::Halted);
+        }
+
+        Ok(self.watch_hit.take().unwrap_or(StepResult::Completed))
+    }
+
+    // Execute exactly one instruction regardless of breakpoints and return its opcode, for debuggers.
+    pub fn step(&mut self) -> Result<u16, ExecError> {
+        let pc = self.pc;
+        let op = self.fetch()?;
+        self.break_pc = None;
+        self.watch_hit = None;
+
+        if let Some(hook) = &mut self.trace_hook {
+            hook(pc, op);
+        }
+
+        self.execute(op)?;
+        self.cycles += 1;
+
+        Ok(op)
+    }
+
+    // Run the instructions and 60Hz timer ticks due for the elapsed wall-clock time at the configured clock rate.
+    // Frontends can call this once per frame with the frame time and get correct speed at any frame rate.
+    // Timer ticks are spread evenly between the instructions. Stops at the first error, breakpoint or watchpoint.
+    // Elapsed times over timing::MAX_ELAPSED (250ms) are clamped to it, so a second-long stall only runs a quarter
+    // second of emulation instead of catching up in one burst. Call it in smaller steps to run longer spans.
+    pub fn advance(&mut self, elapsed: Duration) -> Result<StepResult, ExecError> {
+        let instructions = self.clock.budget(elapsed);
+        let timer_ticks = self.timer_clock.budget(elapsed);
+
+        if timer_ticks == 0 {
+            return self.tick_n(instructions);
+        }
+
+        for step in 0..timer_ticks {
+            let share = instructions * (step + 1) / timer_ticks - instructions * step / timer_ticks;
+
+            let result = self.tick_n(share)?;
+            if result != StepResult::Completed {
+                return Ok(result);
+            }
+
+            self.tick_timers();
+        }
+
+        Ok(StepResult::Completed)
+    }
+
+    // Execute n instructions, stopping early at the first error, breakpoint or watchpoint, or at the instruction limit.
+    // With the display-wait quirk the rest of the budget is dropped once a sprite is drawn this frame.
+    pub fn tick_n(&mut self, n: usize) -> Result<StepResult, ExecError> {
+        let limit = self.instruction_limit.unwrap_or(usize::MAX);
+
+        for _ in 0..n.min(limit) {
+            if self.quirks.display_wait && self.vblank_pending {
+                return Ok(StepResult::Completed);
+            }
+
+            if self.break_pc != Some(self.pc) && self.breakpoints.contains(&self.pc) {
+                self.break_pc = Some(self.pc);
+                return Ok(StepResult::HitBreakpoint(self.pc));
+            }
+
+            let result = self.tick()?;
+            if result != StepResult::Completed {
+                return Ok(result);
+            }
+        }
+
+        if n > limit {
+            return Err(ExecError::InstructionLimit(limit));
+        }
+
+        Ok(StepResult::Completed)
     }
 
     // Fetch opcode from current PC.
     // Ram items are declared as u8 but opcodes or u16 so we fetch 2 items and combine them.
-    fn fetch(&mut self) -> u16 {
-        let higher_byte = self.ram[self.pc as usize] as u16;
-        let lower_byte = self.ram[(self.pc + 1) as usize] as u16;
-        let op = (higher_byte << 8) | lower_byte;
-        self.pc += 2;
-        op
+    fn fetch(&mut self) -> Result<u16, ExecError> {
+        let op = self.read_word(self.pc)?;
+        self.pc = self.pc.wrapping_add(2);
+
+        Ok(op)
+    }
+
+    // Read the big-endian word at the given address as part of the instruction stream.
+    fn read_word(&self, addr: u16) -> Result<u16, ExecError> {
+        if addr as usize + 1 >= self.ram.len() {
+            return Err(ExecError::PcOutOfBounds(addr));
+        }
+
+        let higher_byte = self.ram[addr as usize] as u16;
+        let lower_byte = self.ram[addr as usize + 1] as u16;
+
+        Ok((higher_byte << 8) | lower_byte)
+    }
+
+    // Read a byte from RAM on behalf of an instruction, addresses past the end wrap around to the start.
+    // I can point anywhere in 64KB, so every I based read must go through here.
+    fn read_ram(&self, addr: usize) -> u8 {
+        self.ram[addr % self.ram.len()]
+    }
+
+    // Write a byte to RAM on behalf of an instruction, honoring font protection and watchpoints.
+    // Every instruction write must go through here. Wraps around like read_ram.
+    fn write_ram(&mut self, addr: usize, val: u8) {
+        let addr = addr % self.ram.len();
+
+        if self.protect_font && addr < FONTSET_SIZE {
+            return;
+        }
+
+        let old = self.ram[addr];
+        self.ram[addr] = val;
+
+        if self.watch_hit.is_none() && self.watchpoints.contains(&(addr as u16)) {
+            self.watch_hit = Some(StepResult::Watchpoint {
+                addr: addr as u16,
+                old,
+                new: val,
+            });
+        }
     }
 
     // Push a u16 value to the stack and advance the stack pointer by 1.
-    fn push(&mut self, val: u16) {
+    // The stack holds as many entries as configured on the builder.
+    fn push(&mut self, val: u16) -> Result<(), ExecError> {
+        if self.sp as usize >= self.stack.len() {
+            return Err(ExecError::StackOverflow);
+        }
+
         self.stack[self.sp as usize] = val;
         self.sp += 1;
+
+        Ok(())
     }
 
     // Pop a u16 value from the stack and return the stack pointer to the previous value.
-    fn pop(&mut self) -> u16 {
+    fn pop(&mut self) -> Result<u16, ExecError> {
+        if self.sp == 0 {
+            return Err(ExecError::StackUnderflow);
+        }
+
         self.sp -= 1;
-        self.stack[self.sp as usize]
+
+        Ok(self.stack[self.sp as usize])
     }
 
-    pub fn tick_timers(&mut self) {
+    // Record a sound timer transition if logging is enabled and the beep state changed.
+    fn log_audio(&mut self, was_beeping: bool) {
+        let beeping = self.is_beeping();
+
+        if let Some(log) = &mut self.audio_log {
+            if beeping != was_beeping {
+                log.push((self.cycles, beeping));
+            }
+        }
+    }
+
+    // Decrement the delay and sound timers, frontends should call this 60 times per second independent of
+    // how many instructions they execute in between.
+    pub fn tick_timers(&mut self) -> TimerEvent {
+        self.vblank_pending = false;
+
+        let frame = self.frame_log.is_some().then(|| self.render_ascii());
+        if let (Some(log), Some(frame)) = (&mut self.frame_log, frame) {
+            if frame != log.last {
+                if log.frames.len() == FRAME_LOG_SIZE {
+                    log.frames.pop_front();
+                }
+
+                log.frames.push_back(frame.clone());
+                log.last = frame;
+            }
+        }
+
         if self.dt > 0 {
             self.dt -= 1;
         }
 
+        // Frontends play sound while is_beeping returns true, or between the edges reported here.
+        let beeping_before = self.is_beeping();
+
         if self.st > 0 {
-            if self.st == 1 {
-                // TODO: Implement audio with https://docs.rs/beep/latest/beep/fn.beep.html.
+            self.st -= 1;
+            self.log_audio(beeping_before);
+        }
+
+        let beeping_after = self.is_beeping();
+        let event = TimerEvent {
+            beep_started: !self.beeping_last_tick && beeping_before,
+            beep_stopped: (self.beeping_last_tick || beeping_before) && !beeping_after,
+        };
+        self.beeping_last_tick = beeping_after;
+
+        event
+    }
+
+    // Flag the screen for both take_display_changed and take_display_dirty.
+    fn mark_display_changed(&mut self) {
+        self.display_changed = true;
+        self.display_dirty = true;
+    }
+
+    // Switch resolution, the screen is cleared since its layout changes.
+    fn set_hires(&mut self, hires: bool) {
+        self.hires = hires;
+        self.screen = BLANK_SCREEN;
+        self.mark_display_changed();
+    }
+
+    // Move the selected planes of the active screen area by the given number of pixels, pixels scrolled in are unlit.
+    fn scroll(&mut self, right: isize, down: isize) {
+        let (width, height) = self.get_screen_size();
+        // Pixels shifted past the right edge of the active area are dropped.
+        let row_mask = u128::MAX >> (HIRES_SCREEN_WIDTH - width);
+        let screen = self.screen;
+
+        for (y, row) in self.screen.iter_mut().enumerate().take(height) {
+            let src_y = y as isize - down;
+
+            for (plane, bits) in row.iter_mut().enumerate() {
+                if self.planes & (1 << plane) == 0 {
+                    continue;
+                }
+
+                let src = if (0..height as isize).contains(&src_y) {
+                    screen[src_y as usize][plane]
+                } else {
+                    0
+                };
+                // Column x is bit x, so scrolling right shifts towards the high bits.
+                let scrolled = if right >= 0 { src << right } else { src >> -right };
+                *bits = scrolled & row_mask;
             }
+        }
 
-            self.st -= 1;
+        self.mark_display_changed();
+    }
+
+    // Value shifted by 8XY6/8XYE, VX or VY depending on the quirk.
+    fn shift_source(&self, x: usize, y: usize) -> u8 {
+        if self.quirks.shift_uses_vy {
+            self.v_reg[y]
+        } else {
+            self.v_reg[x]
         }
     }
 
     // Match the given opcode and execute it.
-    fn execute(&mut self, op: u16) {
-        let digit1 = (op & 0xF000) >> 12;
-        let digit2 = (op & 0x0F00) >> 8;
-        let digit3 = (op & 0x00F0) >> 4;
-        let digit4 = op & 0x000F;
+    fn execute(&mut self, op: u16) -> Result<(), ExecError> {
+        #[cfg(feature = "profile")]
+        {
+            self.opcode_histogram[(op >> 12) as usize] += 1;
+        }
+
+        let Some(instruction) = decode(op) else {
+            return self.unknown_opcode(op);
+        };
 
-        match (digit1, digit2, digit3, digit4) {
+        match instruction {
             // 0000 - No operation.
-            (0, 0, 0, 0) => return,
+            Instruction::Nop => return Ok(()),
+
+            // 00E0 - Clear the selected planes.
+            Instruction::Clear => {
+                if self.ignore_clears {
+                    self.ignored_clears += 1;
+                } else {
+                    for row in self.screen.iter_mut() {
+                        for (plane, bits) in row.iter_mut().enumerate() {
+                            if self.planes & (1 << plane) != 0 {
+                                *bits = 0;
+                            }
+                        }
+                    }
+                    self.mark_display_changed();
+                }
+            }
+
+            // 00CN - Scroll the screen down by N pixels.
+            Instruction::ScrollDown(n) => self.scroll(0, n as isize),
+
+            // 00FB - Scroll the screen right by 4 pixels.
+            Instruction::ScrollRight => self.scroll(4, 0),
 
-            // 00E0 - Clear screen.
-            (0, 0, 0xE, 0) => self.screen = [false; SCREEN_WIDTH * SCREEN_HEIGHT],
+            // 00FC - Scroll the screen left by 4 pixels.
+            Instruction::ScrollLeft => self.scroll(-4, 0),
+
+            // 00FD - Exit the interpreter.
+            Instruction::Exit => {
+                self.halted = true;
+                self.pc = self.pc.wrapping_sub(2);
+            }
+
+            // 00FE - Switch to 64x32 low resolution and clear the screen.
+            Instruction::LowRes => self.set_hires(false),
+
+            // 00FF - Switch to 128x64 high resolution and clear the screen.
+            Instruction::HighRes => self.set_hires(true),
 
             // 00EE - Return from subroutine.
-            (0, 0, 0xE, 0xE) => {
-                let ret_addr = self.pop();
+            Instruction::Return => {
+                let ret_addr = self.pop()?;
 
                 self.pc = ret_addr;
             }
 
             // 1NNN - Move PC to given address.
-            (1, _, _, _) => {
-                let nnn = op & 0xFFF;
-
+            Instruction::Jump(nnn) => {
                 self.pc = nnn;
             }
 
             // 2NNN - Save current PC to the stack and move PC to the given address.
-            (2, _, _, _) => {
-                let nnn = op & 0xFFF;
-
-                self.push(self.pc);
+            Instruction::Call(nnn) => {
+                self.push(self.pc)?;
                 self.pc = nnn;
             }
 
             // 3XNN - Skip next if VX == NN.
-            (3, _, _, _) => {
-                let x = digit2 as usize;
-                let nn = (op & 0xFF) as u8;
-
+            Instruction::SkipIfEqual { x, nn } => {
                 if self.v_reg[x] == nn {
-                    self.pc += 2;
+                    self.pc = self.pc.wrapping_add(2);
                 }
             }
 
             // 4XNN - Skip next if VX != NN.
-            (4, _, _, _) => {
-                let x = digit2 as usize;
-                let nn = (op & 0xFF) as u8;
-
+            Instruction::SkipIfNotEqual { x, nn } => {
                 if self.v_reg[x] != nn {
-                    self.pc += 2;
+                    self.pc = self.pc.wrapping_add(2);
                 }
             }
 
             // 5XY0 - Skip next if VX == VY.
-            (5, _, _, 0) => {
-                let x = digit2 as usize;
-                let y = digit3 as usize;
-
+            Instruction::SkipIfRegsEqual { x, y } => {
                 if self.v_reg[x] == self.v_reg[y] {
-                    self.pc += 2;
+                    self.pc = self.pc.wrapping_add(2);
                 }
             }
 
             // 6XNN - Set VX value to NN.
-            (6, _, _, _) => {
-                let x = digit2 as usize;
-                let nn = (op & 0xFF) as u8;
-
+            Instruction::Set { x, nn } => {
                 self.v_reg[x] = nn;
             }
 
             // 7XNN - Add given value to VX.
-            (7, _, _, _) => {
-                let x = digit2 as usize;
-                let nn = (op & 0xFF) as u8;
-
+            Instruction::AddValue { x, nn } => {
                 self.v_reg[x] = self.v_reg[x].wrapping_add(nn);
             }
 
             // 8XY0 - Set VX value to VY value.
-            (8, _, _, 0) => {
-                let x = digit2 as usize;
-                let y = digit3 as usize;
-
+            Instruction::Copy { x, y } => {
                 self.v_reg[x] = self.v_reg[y];
             }
 
             // 8XY1 - Bitwise OR of VX and VY.
-            (8, _, _, 1) => {
-                let x = digit2 as usize;
-                let y = digit3 as usize;
-
+            Instruction::Or { x, y } => {
                 self.v_reg[x] |= self.v_reg[y];
             }
 
             // 8XY2 - Bitwise AND of VX and VY.
-            (8, _, _, 2) => {
-                let x = digit2 as usize;
-                let y = digit3 as usize;
-
+            Instruction::And { x, y } => {
                 self.v_reg[x] &= self.v_reg[y];
             }
 
             // 8XY3 - Bitwise XOR of VX and VY.
-            (8, _, _, 3) => {
-                let x = digit2 as usize;
-                let y = digit3 as usize;
-
+            Instruction::Xor { x, y } => {
                 self.v_reg[x] ^= self.v_reg[y];
             }
 
             // 8XY4 - Add VX + VY and set carry flag in case of integer overflow.
-            (8, _, _, 4) => {
-                let x = digit2 as usize;
-                let y = digit3 as usize;
+            Instruction::Add { x, y } => {
+                // Both operands are read before VF is written, and VF is written last so the flag wins when X is F.
                 let (result, carry) = self.v_reg[x].overflowing_add(self.v_reg[y]);
 
                 self.v_reg[x] = result;
@@ -265,84 +1201,136 @@ impl Emu {
             }
 
             // 8XY5 - Subtract VX - VY and set borrow flag in case of integer underflow.
-            (8, _, _, 5) => {
-                let x = digit2 as usize;
-                let y = digit3 as usize;
+            Instruction::Sub { x, y } => {
+                // Same ordering as 8XY4: VF is an input before it becomes the flag.
                 let (result, borrow) = self.v_reg[x].overflowing_sub(self.v_reg[y]);
 
                 self.v_reg[x] = result;
                 self.v_reg[0xF] = if borrow { 0 } else { 1 };
             }
 
+            // 8XY7 - Subtract VY - VX into VX and set borrow flag in case of integer underflow.
+            Instruction::SubReverse { x, y } => {
+                // Same ordering as 8XY4.
+                let (result, borrow) = self.v_reg[y].overflowing_sub(self.v_reg[x]);
+
+                self.v_reg[x] = result;
+                self.v_reg[0xF] = if borrow { 0 } else { 1 };
+            }
+
             // 8XY6 - Bitwise single right shift and store dropped bit in the flag register.
-            (8, _, _, 6) => {
-                let x = digit2 as usize;
-                let dropped_bit = self.v_reg[x] & 1;
+            Instruction::ShiftRight { x, y } => {
+                let value = self.shift_source(x, y);
+                // Capture the dropped bit before shifting and write VF last, so the flag wins when X is F.
+                let dropped_bit = value & 1;
 
-                self.v_reg[x] >>= 1;
+                self.v_reg[x] = value >> 1;
                 self.v_reg[0xF] = dropped_bit;
             }
 
-            // 9XY0 - Skip next if VX != VY.
-            (9, _, _, 0) => {
-                let x = digit2 as usize;
-                let y = digit3 as usize;
+            // 8XYE - Bitwise single left shift and store dropped bit in the flag register.
+            Instruction::ShiftLeft { x, y } => {
+                let value = self.shift_source(x, y);
+                // Same ordering as 8XY6.
+                let dropped_bit = (value >> 7) & 1;
+
+                self.v_reg[x] = value << 1;
+                self.v_reg[0xF] = dropped_bit;
+            }
 
+            // 9XY0 - Skip next if VX != VY.
+            Instruction::SkipIfRegsNotEqual { x, y } => {
                 if self.v_reg[x] != self.v_reg[y] {
-                    self.pc += 2;
+                    self.pc = self.pc.wrapping_add(2);
                 }
             }
 
             // ANNN - Set the I register to NNN.
-            (0xA, _, _, _) => {
-                let nnn = op & 0xFFF;
-
+            Instruction::SetI(nnn) => {
                 self.i_reg = nnn;
             }
 
-            // BNNN - Jump to V0 + NNN.
-            (0xB, _, _, _) => {
-                let nnn = op & 0xFFF;
+            // BNNN - Jump to V0 + NNN, or BXNN - Jump to VX + NN depending on the quirk.
+            Instruction::JumpOffset { x, nnn } => {
+                let target = if self.quirks.jump_uses_vx {
+                    self.v_reg[x] as u16 + (nnn & 0xFF)
+                } else {
+                    self.v_reg[0] as u16 + nnn
+                };
 
-                self.pc = (self.v_reg[0] as u16) + nnn;
+                // The target can point past the end of RAM, the next fetch then fails with PcOutOfBounds.
+                self.pc = target;
             }
 
             // CXNN - Generate a random number then AND with lower 8 bits of opcode.
-            (0xC, _, _, _) => {
-                let x = digit2 as usize;
-                let nn = (op & 0xFF) as u8;
-                let rng: u8 = random();
+            Instruction::Random { x, nn } => {
+                let rng: u8 = self.rng.gen();
 
                 self.v_reg[x] = rng & nn;
             }
 
             // DXYN - Draw sprite at given coordinate.
-            (0xD, _, _, _) => {
-                let x_coord = self.v_reg[digit2 as usize] as u16;
-                let y_coord = self.v_reg[digit3 as usize] as u16;
-                let num_rows = digit4;
+            Instruction::Draw { x, y, n } => {
+                let (width, height) = self.get_screen_size();
+                let mut x_coord = self.v_reg[x] as u16;
+                let mut y_coord = self.v_reg[y] as u16;
+                if !self.quirks.wrap_x {
+                    x_coord %= width as u16;
+                }
+                if !self.quirks.wrap_y {
+                    y_coord %= height as u16;
+                }
+                let large = n == 0 && self.quirks.large_sprites;
+                let (sprite_width, num_rows) = if large { (16, 16) } else { (8, n as u16) };
+                let sprite_size = if large { 32 } else { num_rows };
+                // With both planes selected, the second plane's sprite data follows the first's.
+                let mut sprite_addr = self.i_reg as usize;
                 // Keep track if any pixels were flipped.
                 let mut flipped = false;
 
-                // Iterate over each row of the sprite.
-                for y_line in 0..num_rows {
-                    let addr = self.i_reg + y_line as u16;
-                    let pixels = self.ram[addr as usize];
-                    // Iterate over each pixel in the current row.
-                    for x_line in 0..8 {
-                        if (pixels & (0b1000_0000 >> x_line)) != 0 {
-                            // Sprites should wrap around screen, so apply modulo.
-                            let x = (x_coord + x_line) as usize % SCREEN_WIDTH;
-                            let y = (y_coord + y_line) as usize % SCREEN_HEIGHT;
-                            // Get our pixel's index for our 1D screen array.
-                            let idx = x + SCREEN_WIDTH * y;
-                            // Check if we're about to flip the pixel and set.
-                            flipped |= self.screen[idx];
-                            self.screen[idx] = true;
+                for plane in 0..NUM_PLANES {
+                    let bit = 1 << plane;
+
+                    if self.planes & bit == 0 {
+                        continue;
+                    }
+
+                    // Iterate over each row of the sprite.
+                    for y_line in 0..num_rows {
+                        // Rows are read into the high bits, so both sprite widths share the mask below.
+                        let pixels = if large {
+                            let addr = sprite_addr + y_line as usize * 2;
+                            (self.read_ram(addr) as u16) << 8 | self.read_ram(addr + 1) as u16
+                        } else {
+                            (self.read_ram(sprite_addr + y_line as usize) as u16) << 8
+                        };
+                        // Iterate over each pixel in the current row.
+                        for x_line in 0..sprite_width {
+                            let clipped_x = !self.quirks.wrap_x && (x_coord + x_line) as usize >= width;
+                            let clipped_y = !self.quirks.wrap_y && (y_coord + y_line) as usize >= height;
+                            if clipped_x || clipped_y {
+                                continue;
+                            }
+
+                            if (pixels & (0x8000 >> x_line)) != 0 {
+                                // Sprites wrap around the screen on axes that aren't clipped, so apply modulo.
+                                let x = (x_coord + x_line) as usize % width;
+                                let y = (y_coord + y_line) as usize % height;
+                                // Column x is bit x of the row in this plane.
+                                let column = 1 << x;
+                                // Check if we're about to turn a lit pixel off, then flip it.
+                                flipped |= self.screen[y][plane] & column != 0;
+                                self.screen[y][plane] ^= column;
+                                self.mark_display_changed();
+                            }
                         }
                     }
+
+                    sprite_addr += sprite_size as usize;
                 }
 
+                self.vblank_pending = true;
+
                 // Populate VF register.
                 if flipped {
                     self.v_reg[0xF] = 1;
@@ -352,36 +1340,52 @@ impl Emu {
             }
 
             // EX9E - Skip if key pressed.
-            (0xE, _, 9, 0xE) => {
-                let x = digit2 as usize;
+            Instruction::SkipIfKey(x) => {
                 let vx = self.v_reg[x];
                 let key = self.keys[vx as usize];
 
                 if key {
-                    self.pc += 2;
+                    self.pc = self.pc.wrapping_add(2);
                 }
             }
 
             // EXA1 - Skip if key not pressed.
-            (0xE, _, 0xA, 1) => {
-                let x = digit2 as usize;
+            Instruction::SkipIfNotKey(x) => {
                 let vx = self.v_reg[x];
                 let key = self.keys[vx as usize];
 
                 if !key {
-                    self.pc += 2;
+                    self.pc = self.pc.wrapping_add(2);
+                }
+            }
+
+            // F000 NNNN - Set I to the 16-bit address in the following word. Only with extended memory, since on a
+            // standard machine the address could point past RAM.
+            Instruction::SetILong if self.ram.len() == XO_RAM_SIZE => {
+                self.i_reg = self.read_word(self.pc)?;
+                self.pc = self.pc.wrapping_add(2);
+            }
+
+            // F002 - Load the audio pattern from RAM starting at I.
+            Instruction::LoadAudio => {
+                let i = self.i_reg as usize;
+                for idx in 0..AUDIO_BUFFER_SIZE {
+                    self.audio_buffer[idx] = self.read_ram(i + idx);
                 }
             }
 
+            // FN01 - Select the planes used by drawing, clearing and scrolling, N is a bit mask.
+            Instruction::SelectPlanes(n) => {
+                self.planes = n & 0b11;
+            }
+
             // FX07 - Set VX to current delay timer value.
-            (0xF, _, 0, 7) => {
-                let x = digit2 as usize;
+            Instruction::GetDelay(x) => {
                 self.v_reg[x] = self.dt;
             }
 
             // FX0A - Wait for key press.
-            (0xF, _, 0, 0xA) => {
-                let x = digit2 as usize;
+            Instruction::WaitKey(x) => {
                 let mut pressed = false;
 
                 for i in 0..self.keys.len() {
@@ -394,71 +1398,1080 @@ impl Emu {
                 }
 
                 if !pressed {
-                    self.pc -= 2;
+                    self.pc = self.pc.wrapping_sub(2);
                 }
             }
 
             // FX15 - Set delay timer to value stored in VX
-            (0xF, _, 1, 5) => {
-                let x = digit2 as usize;
+            Instruction::SetDelay(x) => {
                 self.dt = self.v_reg[x];
             }
 
             // FX18 - Set sound timer to value stored in VX
-            (0xF, _, 1, 8) => {
-                let x = digit2 as usize;
+            Instruction::SetSound(x) => {
+                let was_beeping = self.is_beeping();
                 self.st = self.v_reg[x];
+                self.log_audio(was_beeping);
             }
 
             // FX1E - Increment I by VX value.
-            (0xF, _, 1, 0xE) => {
-                let x = digit2 as usize;
+            Instruction::AddI(x) => {
                 let vx = self.v_reg[x] as u16;
                 self.i_reg = self.i_reg.wrapping_add(vx);
             }
 
             // FX29 - Set I to Font Address.
-            (0xF, _, 2, 9) => {
-                let x = digit2 as usize;
+            Instruction::SetIFont(x) => {
                 let vx = self.v_reg[x] as u16;
                 self.i_reg = vx * 5;
             }
 
+            // FX3A - Set the audio pitch to VX.
+            Instruction::SetPitch(x) => {
+                self.pitch = self.v_reg[x];
+            }
+
             // FX33 - Binary-coded decimal.
-            (0xF, _, 3, 3) => {
-                let x = digit2 as usize;
-                let vx = self.v_reg[x] as f32;
-                // Fetch the hundreds digit by dividing by 100 and tossing the decimal
-                let hundreds = (vx / 100.0).floor() as u8;
-                // Fetch the tens digit by dividing by 10, tossing the ones digit and the decimal
-                let tens = ((vx / 10.0) % 10.0).floor() as u8;
+            Instruction::StoreBcd(x) => {
+                let vx = self.v_reg[x];
+                // Fetch the hundreds digit by dividing by 100, integer division tosses the remainder
+                let hundreds = vx / 100;
+                // Fetch the tens digit by dividing by 10 and tossing the hundreds
+                let tens = (vx / 10) % 10;
                 // Fetch the ones digit by tossing the hundreds and the tens
-                let ones = (vx % 10.0) as u8;
-                self.ram[self.i_reg as usize] = hundreds;
-                self.ram[(self.i_reg + 1) as usize] = tens;
-                self.ram[(self.i_reg + 2) as usize] = ones;
+                let ones = vx % 10;
+                self.write_ram(self.i_reg as usize, hundreds);
+                self.write_ram(self.i_reg as usize + 1, tens);
+                self.write_ram(self.i_reg as usize + 2, ones);
             }
 
             // FX55 - Store V0 - VX values into RAM.
-            (0xF, _, 5, 5) => {
-                let x = digit2 as usize;
+            Instruction::StoreRegs(x) => {
                 let i = self.i_reg as usize;
                 for idx in 0..=x {
-                    self.ram[i + idx] = self.v_reg[idx];
+                    self.write_ram(i + idx, self.v_reg[idx]);
+                }
+
+                if self.quirks.load_store_increments_i {
+                    self.i_reg = self.i_reg.wrapping_add(x as u16 + 1);
                 }
             },
 
-            // FX55 - Load V0 - VX values from RAM.
-            (0xF, _, 6, 5) => {
-                let x = digit2 as usize;
+            // FX65 - Load V0 - VX values from RAM.
+            Instruction::LoadRegs(x) => {
                 let i = self.i_reg as usize;
                 for idx in 0..=x {
-                    self.v_reg[idx] = self.ram[i + idx];
+                    self.v_reg[idx] = self.read_ram(i + idx);
                 }
+
+                if self.quirks.load_store_increments_i {
+                    self.i_reg = self.i_reg.wrapping_add(x as u16 + 1);
+                }
+            },
+
+            // FX75 - Store V0 - VX in the RPL user flags, only V0 - V7 fit.
+            Instruction::StoreFlags(x) => {
+                let count = (x + 1).min(NUM_RPL_FLAGS);
+                self.rpl_flags[..count].copy_from_slice(&self.v_reg[..count]);
             },
 
-            // Fallback value required by Rust, this should never execute.
-            (_, _, _, _) => unimplemented!("Unimplemented opcode: {}", op),
+            // FX85 - Load V0 - VX from the RPL user flags, only V0 - V7 fit.
+            Instruction::LoadFlags(x) => {
+                let count = (x + 1).min(NUM_RPL_FLAGS);
+                self.v_reg[..count].copy_from_slice(&self.rpl_flags[..count]);
+            },
+
+            Instruction::SetILong => return self.unknown_opcode(op),
+        }
+
+        Ok(())
+    }
+
+    // Handle an opcode that isn't recognized, see is_supported.
+    fn unknown_opcode(&mut self, op: u16) -> Result<(), ExecError> {
+        match self.unknown_opcodes {
+            UnknownOpcodes::Panic => unimplemented!("Unimplemented opcode: {}", op),
+            UnknownOpcodes::Error => return Err(ExecError::UnknownOpcode(op)),
+            UnknownOpcodes::Skip => self.unknown_opcode_count += 1,
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for Emu {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Emulator with the given opcodes loaded at the start address.
+    fn emu_with(ops: &[u16]) -> Emu {
+        let program: Vec<u8> = ops.iter().flat_map(|op| op.to_be_bytes()).collect();
+        let mut emu = Emu::with_seed(0);
+        emu.set_unknown_opcodes(UnknownOpcodes::Error);
+        emu.load(&program).unwrap();
+        emu
+    }
+
+    // Run a single opcode with the given registers set first and return the registers afterwards.
+    fn run_op(op: u16, regs: &[(usize, u8)]) -> [u8; NUM_REGS] {
+        run_op_with(Quirks::default(), op, regs)
+    }
+
+    fn run_op_with(quirks: Quirks, op: u16, regs: &[(usize, u8)]) -> [u8; NUM_REGS] {
+        let mut emu = emu_with(&[op]);
+        emu.set_quirks(quirks);
+        for &(reg, val) in regs {
+            emu.v_reg[reg] = val;
+        }
+        emu.tick().unwrap();
+        emu.v_reg
+    }
+
+    #[test]
+    fn alu_flag_ordering() {
+        // (N, VX, VY, result, flag), where no flag means VF is left alone.
+        let cases = [
+            (0x0, 0xF0, 0x20, 0x20, None),
+            (0x1, 0xF0, 0x21, 0xF1, None),
+            (0x2, 0xF0, 0x21, 0x20, None),
+            (0x3, 0xF0, 0x21, 0xD1, None),
+            (0x4, 0xF0, 0x20, 0x10, Some(1)),
+            (0x4, 0x10, 0x20, 0x30, Some(0)),
+            (0x5, 0x10, 0x20, 0xF0, Some(0)),
+            (0x5, 0x20, 0x10, 0x10, Some(1)),
+            (0x7, 0x10, 0x20, 0x10, Some(1)),
+            (0x7, 0x20, 0x10, 0xF0, Some(0)),
+            (0x6, 0x05, 0x00, 0x02, Some(1)),
+            (0x6, 0x04, 0x00, 0x02, Some(0)),
+            (0xE, 0x81, 0x00, 0x02, Some(1)),
+            (0xE, 0x41, 0x00, 0x82, Some(0)),
+        ];
+
+        for (n, vx, vy, result, flag) in cases {
+            // VF as neither operand.
+            let regs = run_op(0x8120 | n, &[(1, vx), (2, vy), (0xF, 0x55)]);
+            assert_eq!(regs[1], result, "8x{:X} result", n);
+            assert_eq!(regs[0xF], flag.unwrap_or(0x55), "8x{:X} flag", n);
+
+            // VF as X, the flag wins over the result.
+            let regs = run_op(0x8F20 | n, &[(0xF, vx), (2, vy)]);
+            assert_eq!(regs[0xF], flag.unwrap_or(result), "8F{:X} flag", n);
+
+            // VF as Y, read before it becomes the flag.
+            let regs = run_op(0x81F0 | n, &[(1, vx), (0xF, vy)]);
+            assert_eq!(regs[1], result, "8xF{:X} result", n);
+            assert_eq!(regs[0xF], flag.unwrap_or(vy), "8xF{:X} flag", n);
+        }
+    }
+
+    #[test]
+    fn shift_flag_ordering() {
+        for shift_uses_vy in [false, true] {
+            let quirks = Quirks {
+                shift_uses_vy,
+                ..Quirks::default()
+            };
+
+            // 8FY6: the dropped bit wins over the result in VF. V2 and VF hold different values so the source shows.
+            let regs = run_op_with(quirks, 0x8F26, &[(0xF, 0x02), (2, 0x03)]);
+            assert_eq!(regs[0xF], if shift_uses_vy { 1 } else { 0 });
+
+            // 8XF6: VF is read as the source before the dropped bit replaces it.
+            let regs = run_op_with(quirks, 0x81F6, &[(1, 0x40), (0xF, 0x81)]);
+            assert_eq!(regs[1], if shift_uses_vy { 0x40 } else { 0x20 });
+            assert_eq!(regs[0xF], if shift_uses_vy { 1 } else { 0 });
+
+            // 8FFE: source and destination are both VF, only the dropped bit remains.
+            let regs = run_op_with(quirks, 0x8FFE, &[(0xF, 0x80)]);
+            assert_eq!(regs[0xF], 1);
+            let regs = run_op_with(quirks, 0x8FFE, &[(0xF, 0x7F)]);
+            assert_eq!(regs[0xF], 0);
+
+            // 8XYE with the carry-out bit set and clear.
+            let regs = run_op_with(quirks, 0x812E, &[(1, 0x81), (2, 0x81)]);
+            assert_eq!((regs[1], regs[0xF]), (0x02, 1));
+            let regs = run_op_with(quirks, 0x812E, &[(1, 0x41), (2, 0x41)]);
+            assert_eq!((regs[1], regs[0xF]), (0x82, 0));
+        }
+    }
+
+    #[test]
+    fn ignored_clears() {
+        // Draw the 0 glyph, clear, then draw it again.
+        let mut emu = emu_with(&[0xD005, 0x00E0, 0xD005]);
+        emu.set_ignore_clears(true);
+        emu.tick_n(2).unwrap();
+
+        let drawn = emu.get_display().to_vec();
+        assert_ne!(drawn, vec![0; drawn.len()]);
+        assert_eq!(emu.ignored_clear_count(), 1);
+
+        // The screen is untouched, so drawing again collides and erases the glyph.
+        emu.tick().unwrap();
+        assert_eq!(emu.get_display(), vec![0; drawn.len()]);
+        assert_eq!(emu.get_registers()[0xF], 1);
+
+        emu.set_ignore_clears(false);
+        emu.reset();
+        assert_eq!(emu.ignored_clear_count(), 0);
+    }
+
+    #[test]
+    fn advance_runs_instructions_and_timers_for_elapsed_time() {
+        // Jump to itself forever.
+        let mut emu = EmuBuilder::new().clock_hz(1200).build();
+        emu.load(&[0x12, 0x00]).unwrap();
+        emu.dt = 100;
+
+        emu.advance(Duration::from_millis(50)).unwrap();
+        assert_eq!(emu.cycles(), 60);
+        assert_eq!(emu.get_delay_timer(), 97);
+
+        // Longer stalls are clamped to MAX_ELAPSED.
+        emu.advance(Duration::from_secs(1)).unwrap();
+        assert_eq!(emu.cycles(), 60 + 300);
+        assert_eq!(emu.get_delay_timer(), 97 - 15);
+    }
+
+    #[test]
+    fn long_addresses_need_extended_ram() {
+        let mut emu = emu_with(&[0xF000, 0xFFFF]);
+        assert_eq!(emu.tick(), Err(ExecError::UnknownOpcode(0xF000)));
+        assert_eq!(emu.get_i(), 0);
+
+        let mut emu = EmuBuilder::new().extended_ram(true).build();
+        emu.load(&[0xF0, 0x00, 0xFF, 0xFF]).unwrap();
+        emu.tick().unwrap();
+        assert_eq!(emu.get_i(), 0xFFFF);
+        assert_eq!(emu.get_pc(), START_ADDR + 4);
+    }
+
+    #[test]
+    fn reads_from_i_wrap_around_ram() {
+        for extended_ram in [false, true] {
+            let mut emu = EmuBuilder::new().extended_ram(extended_ram).build();
+            let last = emu.get_ram().len() - 1;
+            emu.ram[last] = 0xAB;
+            emu.ram[0] = 0xCD;
+            emu.set_quirks(Quirks {
+                large_sprites: true,
+                ..Quirks::default()
+            });
+            // Load V0 - V1, draw an 8x2 and a 16x16 sprite, all starting at the last byte of RAM.
+            emu.load(&[0xF1, 0x65, 0xD0, 0x12, 0xD0, 0x10]).unwrap();
+            emu.i_reg = last as u16;
+
+            emu.tick_n(3).unwrap();
+            assert_eq!(&emu.get_registers()[..2], &[0xAB, 0xCD]);
+        }
+
+        // The 16x16 sprite's rows run past 0xFFFF.
+        let mut emu = EmuBuilder::new().extended_ram(true).build();
+        emu.set_quirks(Quirks {
+            large_sprites: true,
+            ..Quirks::default()
+        });
+        emu.load(&[0xD0, 0x10]).unwrap();
+        emu.i_reg = 0xFFF0;
+        emu.tick().unwrap();
+    }
+
+    #[test]
+    fn audio_pattern_wraps_around_ram() {
+        let mut emu = emu_with(&[0xF002]);
+        let len = emu.get_ram().len();
+        for idx in 0..AUDIO_BUFFER_SIZE {
+            emu.ram[(len - 4 + idx) % len] = idx as u8 + 1;
+        }
+        emu.i_reg = (len - 4) as u16;
+        emu.tick().unwrap();
+
+        let expected: Vec<u8> = (1..=AUDIO_BUFFER_SIZE as u8).collect();
+        assert_eq!(&emu.get_audio_buffer()[..], &expected[..]);
+    }
+
+    #[test]
+    fn watchpoint_reports_store() {
+        // Store V0 - V2 at 0x300, the write to 0x301 is watched.
+        let mut emu = emu_with(&[0xA300, 0xF255, 0xF255]);
+        emu.v_reg[..3].copy_from_slice(&[1, 2, 3]);
+        emu.set_watchpoint(0x301);
+
+        assert_eq!(emu.tick(), Ok(StepResult::Completed));
+        assert_eq!(
+            emu.tick(),
+            Ok(StepResult::Watchpoint {
+                addr: 0x301,
+                old: 0,
+                new: 2
+            })
+        );
+        assert_eq!(emu.get_ram()[0x300..0x303], [1, 2, 3]);
+
+        // Writes are still reported when the value doesn't change.
+        emu.v_reg[1] = 7;
+        assert_eq!(
+            emu.tick(),
+            Ok(StepResult::Watchpoint {
+                addr: 0x301,
+                old: 2,
+                new: 7
+            })
+        );
+    }
+
+    #[test]
+    fn writes_from_i_wrap_around_ram() {
+        // BCD of 123 and a store of V0 - V2, both starting at the second to last byte of RAM.
+        let mut emu = emu_with(&[0xF033, 0xF255]);
+        let len = emu.get_ram().len();
+        emu.v_reg[..3].copy_from_slice(&[123, 5, 6]);
+        emu.i_reg = (len - 2) as u16;
+        emu.set_watchpoint(0);
+
+        assert_eq!(
+            emu.tick(),
+            Ok(StepResult::Watchpoint {
+                addr: 0,
+                old: FONTSET[0],
+                new: 3
+            })
+        );
+        assert_eq!(emu.get_ram()[len - 2..], [1, 2]);
+
+        emu.tick().unwrap();
+        assert_eq!(emu.get_ram()[len - 2..], [123, 5]);
+        assert_eq!(emu.get_ram()[0], 6);
+    }
+
+    #[test]
+    fn stack_overflow_and_underflow() {
+        // Call itself until the 16 entry stack is full.
+        let mut emu = emu_with(&[0x2200]);
+        assert_eq!(emu.tick_n(16), Ok(StepResult::Completed));
+        assert_eq!(emu.tick(), Err(ExecError::StackOverflow));
+        assert_eq!(emu.get_sp(), 16);
+
+        let mut emu = emu_with(&[0x00EE]);
+        assert_eq!(emu.tick(), Err(ExecError::StackUnderflow));
+        assert_eq!(emu.get_sp(), 0);
+    }
+
+    #[test]
+    fn i_and_pc_wrap_at_the_top_of_memory() {
+        let mut emu = EmuBuilder::new().extended_ram(true).build();
+        emu.set_quirks(Quirks {
+            load_store_increments_i: true,
+            ..Quirks::default()
+        });
+        // Store and load V0 - V3 with I four bytes below 0x10000.
+        emu.load(&[0xF3, 0x55, 0xF3, 0x65]).unwrap();
+        emu.i_reg = 0xFFFE;
+        emu.tick().unwrap();
+        assert_eq!(emu.get_i(), 0x0002);
+        emu.i_reg = 0xFFFE;
+        emu.tick().unwrap();
+        assert_eq!(emu.get_i(), 0x0002);
+
+        // A skip in the last word of 64KB wraps the PC to the start of RAM.
+        emu.ram[0xFFFE..].copy_from_slice(&[0x30, 0x00]);
+        emu.pc = 0xFFFE;
+        emu.v_reg[0] = 0;
+        emu.tick().unwrap();
+        assert_eq!(emu.get_pc(), 0x0002);
+    }
+
+    #[test]
+    fn configurable_stack_size() {
+        // Call itself, nesting deeper than the standard 16 entries.
+        let mut emu = EmuBuilder::new().stack_size(32).build();
+        emu.load(&[0x22, 0x00]).unwrap();
+        assert_eq!(emu.tick_n(32), Ok(StepResult::Completed));
+        assert_eq!(emu.get_sp(), 32);
+        assert_eq!(emu.get_stack().len(), 32);
+        assert_eq!(emu.tick(), Err(ExecError::StackOverflow));
+    }
+
+    #[test]
+    fn large_sprite_collides_and_wraps() {
+        // Draw a 16x16 sprite at (60, 28) twice, it wraps past the right and bottom edges.
+        let program = [0xA300, 0x603C, 0x611C, 0xD010, 0xD010];
+        let mut emu = emu_with(&program);
+        emu.set_quirks(Quirks {
+            large_sprites: true,
+            ..Quirks::default()
+        });
+        // Solid sprite except the top-left pixel of each row.
+        for row in 0..16 {
+            emu.ram[0x300 + row * 2..0x302 + row * 2].copy_from_slice(&[0x7F, 0xFF]);
+        }
+
+        emu.tick_n(4).unwrap();
+        assert_eq!(emu.get_registers()[0xF], 0);
+        let lit = emu
+            .get_display()
+            .iter()
+            .filter(|&&pixel| pixel != 0)
+            .count();
+        assert_eq!(lit, 15 * 16);
+        assert_eq!(emu.pixel(60, 28), 0);
+        assert_eq!(emu.pixel(61, 28), 1);
+        assert_eq!(emu.pixel(11, 11), 1);
+        assert_eq!(emu.pixel(12, 11), 0);
+        assert_eq!(emu.pixel(11, 12), 0);
+
+        emu.tick().unwrap();
+        assert_eq!(emu.get_registers()[0xF], 1);
+
+        // Without the quirk DXY0 draws nothing.
+        let mut emu = emu_with(&program);
+        emu.ram[0x300..0x320].fill(0xFF);
+        emu.tick_n(4).unwrap();
+        assert!(emu.get_display().iter().all(|&pixel| pixel == 0));
+        assert_eq!(emu.get_registers()[0xF], 0);
+    }
+
+    // Lit pixels as (x, y), in row order.
+    fn lit_pixels(emu: &Emu) -> Vec<(usize, usize)> {
+        let width = emu.display_width();
+
+        emu.get_display()
+            .iter()
+            .enumerate()
+            .filter(|(_, &pixel)| pixel != 0)
+            .map(|(idx, _)| (idx % width, idx / width))
+            .collect()
+    }
+
+    #[test]
+    fn scrolling() {
+        // Scroll down by 2, right by 4, then left by 4 twice.
+        let mut emu = emu_with(&[0x00C2, 0x00FB, 0x00FC, 0x00FC]);
+        for (x, y) in [(0, 0), (5, 1), (63, 30)] {
+            emu.screen[y][0] |= 1 << x;
+        }
+
+        emu.tick().unwrap();
+        assert_eq!(lit_pixels(&emu), [(0, 2), (5, 3)]);
+        emu.tick().unwrap();
+        assert_eq!(lit_pixels(&emu), [(4, 2), (9, 3)]);
+        emu.tick().unwrap();
+        assert_eq!(lit_pixels(&emu), [(0, 2), (5, 3)]);
+        emu.tick().unwrap();
+        assert_eq!(lit_pixels(&emu), [(1, 3)]);
+    }
+
+    #[test]
+    fn scrolling_uses_the_active_resolution() {
+        // Switch to high resolution, then scroll down by 15 and right by 4.
+        let mut emu = emu_with(&[0x00FF, 0x00CF, 0x00FB]);
+        emu.tick().unwrap();
+        for (x, y) in [(100, 40), (127, 0)] {
+            emu.screen[y][0] |= 1 << x;
         }
+
+        emu.tick_n(2).unwrap();
+        assert_eq!(lit_pixels(&emu), [(104, 55)]);
+    }
+
+    #[test]
+    fn display_wait_allows_one_draw_per_frame() {
+        for display_wait in [false, true] {
+            // Draw, add 1 to V1 and loop.
+            let mut emu = emu_with(&[0xD015, 0x7101, 0x1200]);
+            emu.set_quirks(Quirks {
+                display_wait,
+                ..Quirks::default()
+            });
+            let draws = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+            let counter = draws.clone();
+            emu.set_trace_hook(Box::new(move |_, op| {
+                if op & 0xF000 == 0xD000 {
+                    counter.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                }
+            }));
+
+            for frame in 1..=3 {
+                assert_eq!(emu.tick_n(30), Ok(StepResult::Completed));
+                assert!(emu.needs_vblank());
+
+                let count = draws.load(std::sync::atomic::Ordering::Relaxed);
+                if display_wait {
+                    assert_eq!(count, frame);
+                } else {
+                    assert_eq!(count, frame * 10);
+                }
+
+                emu.tick_timers();
+                assert!(!emu.needs_vblank());
+            }
+        }
+    }
+
+    // Draw the 8x2 solid sprite at (x, y) with the given wrapping and return the lit pixels.
+    fn draw_solid(x: u8, y: u8, wrap_x: bool, wrap_y: bool) -> Vec<(usize, usize)> {
+        let mut emu = emu_with(&[0xA300, 0x6000 | x as u16, 0x6100 | y as u16, 0xD012]);
+        emu.set_quirks(Quirks {
+            wrap_x,
+            wrap_y,
+            ..Quirks::default()
+        });
+        emu.ram[0x300..0x302].fill(0xFF);
+        emu.tick_n(4).unwrap();
+
+        lit_pixels(&emu)
+    }
+
+    #[test]
+    fn sprites_wrap_or_clip_at_the_edges() {
+        // Partially off the right edge.
+        let right: Vec<_> = (60..64).chain(0..4).collect();
+        let mut wrapped: Vec<_> = [10, 11]
+            .iter()
+            .flat_map(|&y| right.iter().map(move |&x| (x, y)))
+            .collect();
+        wrapped.sort_by_key(|&(x, y)| (y, x));
+        assert_eq!(draw_solid(60, 10, true, true), wrapped);
+        let clipped: Vec<_> = [10, 11]
+            .iter()
+            .flat_map(|&y| (60..64).map(move |x| (x, y)))
+            .collect();
+        assert_eq!(draw_solid(60, 10, false, false), clipped);
+
+        // Partially off the bottom edge.
+        let wrapped: Vec<_> = [0, 31]
+            .iter()
+            .flat_map(|&y| (10..18).map(move |x| (x, y)))
+            .collect();
+        assert_eq!(draw_solid(10, 31, true, true), wrapped);
+        let clipped: Vec<_> = (10..18).map(|x| (x, 31)).collect();
+        assert_eq!(draw_solid(10, 31, false, false), clipped);
+    }
+
+    #[test]
+    fn sprites_wrap_or_clip_per_axis_at_a_corner() {
+        for wrap_x in [false, true] {
+            for wrap_y in [false, true] {
+                let xs: Vec<usize> = if wrap_x {
+                    (0..4).chain(60..64).collect()
+                } else {
+                    (60..64).collect()
+                };
+                let ys: Vec<usize> = if wrap_y { vec![0, 31] } else { vec![31] };
+                let expected: Vec<_> = ys
+                    .iter()
+                    .flat_map(|&y| xs.iter().map(move |&x| (x, y)))
+                    .collect();
+
+                assert_eq!(
+                    draw_solid(60, 31, wrap_x, wrap_y),
+                    expected,
+                    "wrap_x {} wrap_y {}",
+                    wrap_x,
+                    wrap_y
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn jump_with_offset() {
+        for (jump_uses_vx, target) in [(false, 0x244), (true, 0x54)] {
+            let mut emu = emu_with(&[0xB234]);
+            emu.set_quirks(Quirks {
+                jump_uses_vx,
+                ..Quirks::default()
+            });
+            emu.v_reg[0] = 0x10;
+            emu.v_reg[2] = 0x20;
+
+            emu.tick().unwrap();
+            assert_eq!(emu.get_pc(), target);
+        }
+    }
+
+    #[test]
+    fn render_font_glyph_as_ascii() {
+        // Point I at the glyph for 8 and draw it at (1, 1).
+        let mut emu = emu_with(&[0x6008, 0xF029, 0x6101, 0xD115]);
+        emu.tick_n(4).unwrap();
+
+        let ascii = emu.render_ascii();
+        let rows: Vec<&str> = ascii.lines().collect();
+        assert_eq!(rows.len(), SCREEN_HEIGHT);
+        assert!(rows.iter().all(|row| row.len() == SCREEN_WIDTH));
+        assert_eq!(rows[0].trim_end(), "");
+        assert_eq!(rows[1].trim_end(), " ####");
+        assert_eq!(rows[2].trim_end(), " #  #");
+        assert_eq!(rows[3].trim_end(), " ####");
+        assert_eq!(rows[4].trim_end(), " #  #");
+        assert_eq!(rows[5].trim_end(), " ####");
+        assert_eq!(rows[6].trim_end(), "");
+        assert!(ascii.ends_with('\n'));
+    }
+
+    #[test]
+    fn display_dirty_flag() {
+        // Draw, clear, then do nothing.
+        let mut emu = emu_with(&[0xD005, 0x00E0, 0x0000]);
+        // Dirty from the start so the first frame is drawn.
+        assert!(emu.take_display_dirty());
+        assert!(!emu.take_display_dirty());
+
+        emu.tick().unwrap();
+        assert!(emu.take_display_dirty());
+        assert!(!emu.take_display_dirty());
+
+        emu.tick().unwrap();
+        assert!(emu.take_display_dirty());
+
+        emu.tick().unwrap();
+        assert!(!emu.take_display_dirty());
+
+        // Independent of take_display_changed.
+        emu.reset();
+        assert!(emu.take_display_changed());
+        assert!(emu.take_display_dirty());
+        assert!(!emu.take_display_dirty());
+    }
+
+    #[test]
+    fn immediate_halt() {
+        let mut emu = emu_with(&[0x00FD, 0x6001]);
+        assert_eq!(emu.tick(), Ok(StepResult::Halted));
+        assert!(emu.is_halted());
+        assert_eq!(emu.get_pc(), START_ADDR);
+
+        // Nothing runs until reset.
+        assert_eq!(emu.tick_n(10), Ok(StepResult::Halted));
+        assert_eq!(emu.cycles(), 1);
+        assert_eq!(emu.get_registers()[0], 0);
+
+        emu.reset();
+        assert!(!emu.is_halted());
+    }
+
+    #[test]
+    fn spans_of_a_known_pattern() {
+        let width = 8;
+        #[rustfmt::skip]
+        let screen = [
+            1, 1, 1, 0, 0, 2, 2, 1,
+            0, 0, 0, 0, 0, 0, 0, 0,
+            3, 0, 3, 3, 0, 0, 0, 3,
+        ];
+
+        assert_eq!(
+            display_spans(&screen, width),
+            [
+                (0, 0, 3),
+                (0, 5, 2),
+                (0, 7, 1),
+                (2, 0, 1),
+                (2, 2, 2),
+                (2, 7, 1)
+            ]
+        );
+
+        // Spans on the emulator's display use its active resolution.
+        let mut emu = emu_with(&[0x00FF]);
+        emu.tick().unwrap();
+        emu.screen[3][0] = 0xF << 100;
+        assert_eq!(emu.get_display_spans(), [(3, 100, 4)]);
+    }
+
+    #[test]
+    fn builder_examples() {
+        let quirks = Quirks {
+            large_sprites: true,
+            wrap_x: false,
+            wrap_y: false,
+            ..Quirks::default()
+        };
+        let build = || {
+            EmuBuilder::new()
+                .quirks(quirks)
+                .seed(42)
+                .highres(true)
+                .build()
+        };
+        let mut emu = build();
+        assert_eq!(emu.quirks, quirks);
+        assert_eq!(
+            emu.get_screen_size(),
+            (HIRES_SCREEN_WIDTH, HIRES_SCREEN_HEIGHT)
+        );
+        assert_eq!(emu.get_ram().len(), RAM_SIZE);
+
+        // The same seed gives the same CXNN results.
+        let mut other = build();
+        for emu in [&mut emu, &mut other] {
+            emu.load(&[0xC0, 0xFF, 0xC1, 0xFF]).unwrap();
+            emu.tick_n(2).unwrap();
+        }
+        assert_eq!(emu.get_registers(), other.get_registers());
+
+        let emu = EmuBuilder::new().extended_ram(true).build();
+        assert_eq!(emu.get_ram().len(), XO_RAM_SIZE);
+        assert_eq!(emu.quirks, Quirks::default());
+        assert_eq!(emu.get_screen_size(), (SCREEN_WIDTH, SCREEN_HEIGHT));
+    }
+
+    #[test]
+    fn keys_round_trip() {
+        let mut emu = Emu::new();
+        let mut keys = [false; NUM_KEYS];
+        keys[0] = true;
+        keys[7] = true;
+        keys[0xF] = true;
+
+        emu.set_keys(keys);
+        assert_eq!(emu.get_keys(), &keys);
+
+        emu.keypress(7, false);
+        keys[7] = false;
+        assert_eq!(emu.get_keys(), &keys);
+    }
+
+    #[test]
+    fn cycle_counter_and_instruction_limit() {
+        // Jump to itself forever.
+        let mut emu = emu_with(&[0x1200]);
+        assert_eq!(emu.cycles(), 0);
+
+        emu.tick().unwrap();
+        emu.step().unwrap();
+        emu.tick_n(5).unwrap();
+        assert_eq!(emu.cycles(), 7);
+
+        emu.set_instruction_limit(Some(100));
+        assert_eq!(emu.tick_n(100), Ok(StepResult::Completed));
+        assert_eq!(emu.tick_n(1000), Err(ExecError::InstructionLimit(100)));
+        assert_eq!(emu.cycles(), 207);
+
+        emu.reset();
+        assert_eq!(emu.cycles(), 0);
+    }
+
+    #[test]
+    fn rpl_flags_round_trip() {
+        // Store V0 - V3, clear them, then load V0 - V3 back. FF75 and FF85 only reach V0 - V7.
+        let mut emu = emu_with(&[0xF375, 0x6000, 0x6100, 0x6200, 0x6300, 0xF385, 0xFF75, 0xFF85]);
+        emu.v_reg[..4].copy_from_slice(&[1, 2, 3, 4]);
+
+        emu.tick_n(6).unwrap();
+        assert_eq!(emu.get_registers()[..4], [1, 2, 3, 4]);
+
+        emu.v_reg = [9; NUM_REGS];
+        emu.tick().unwrap();
+        assert_eq!(emu.rpl_flags, [9; NUM_RPL_FLAGS]);
+        emu.v_reg = [0; NUM_REGS];
+        emu.tick().unwrap();
+        assert_eq!(emu.get_registers()[..8], [9; 8]);
+        assert_eq!(emu.get_registers()[8..], [0; 8]);
+
+        // Flags are cleared on reset.
+        emu.reset();
+        assert_eq!(emu.rpl_flags, [0; NUM_RPL_FLAGS]);
+    }
+
+    #[test]
+    fn garbage_opcodes() {
+        // 5XY1 isn't an instruction, the next one sets V0.
+        let mut emu = emu_with(&[0x5121, 0x6007]);
+        assert_eq!(emu.tick(), Err(ExecError::UnknownOpcode(0x5121)));
+        assert_eq!(emu.get_pc(), START_ADDR + 2);
+
+        let mut emu = emu_with(&[0x5121, 0x6007, 0xFFFF]);
+        emu.set_unknown_opcodes(UnknownOpcodes::Skip);
+        assert_eq!(emu.tick_n(3), Ok(StepResult::Completed));
+        assert_eq!(emu.get_registers()[0], 7);
+        assert_eq!(emu.unknown_opcode_count(), 2);
+
+        emu.reset();
+        assert_eq!(emu.unknown_opcode_count(), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Unimplemented opcode")]
+    fn garbage_opcode_panics_by_default() {
+        let mut emu = Emu::new();
+        emu.load(&[0x51, 0x21]).unwrap();
+        let _ = emu.tick();
+    }
+
+    #[cfg(feature = "profile")]
+    #[test]
+    fn opcode_histogram_counts_executed_families() {
+        // Set V0, add to it twice in a loop, jumping back each time.
+        let mut emu = emu_with(&[0x6001, 0x7001, 0x7001, 0x1202]);
+        emu.tick_n(7).unwrap();
+
+        let mut expected = [0; 16];
+        expected[0x6] = 1;
+        expected[0x7] = 4;
+        expected[0x1] = 2;
+        assert_eq!(emu.opcode_histogram(), &expected);
+
+        emu.reset();
+        assert_eq!(emu.opcode_histogram(), &[0; 16]);
+    }
+
+    #[test]
+    fn planes_are_drawn_cleared_and_scrolled_separately() {
+        // Select both planes and draw a row of each, then clear the first and scroll the second right.
+        let mut emu = emu_with(&[0xA300, 0xF301, 0xD011, 0xF101, 0x00E0, 0xF201, 0x00FB]);
+        emu.ram[0x300] = 0xFF;
+        emu.ram[0x301] = 0x80;
+
+        emu.tick_n(3).unwrap();
+        assert_eq!((emu.pixel(0, 0), emu.pixel(1, 0), emu.pixel(8, 0)), (3, 1, 0));
+        emu.tick_n(2).unwrap();
+        assert_eq!((emu.pixel(0, 0), emu.pixel(1, 0)), (2, 0));
+        emu.tick_n(2).unwrap();
+        assert_eq!((emu.pixel(0, 0), emu.pixel(4, 0)), (0, 2));
+
+        let display = emu.get_display();
+        assert_eq!(display.len(), SCREEN_WIDTH * SCREEN_HEIGHT);
+        assert_eq!(display.iter().filter(|&&pixel| pixel != 0).collect::<Vec<_>>(), [&2]);
+    }
+
+    #[test]
+    fn display_changed_is_taken_once_per_change() {
+        // Draw, then set V0 without drawing.
+        let mut emu = emu_with(&[0xD015, 0x6001]);
+
+        emu.tick().unwrap();
+        assert!(emu.take_display_changed());
+        assert!(!emu.take_display_changed());
+        emu.tick().unwrap();
+        assert!(!emu.take_display_changed());
+    }
+
+    #[test]
+    fn audio_log_records_beep_edges() {
+        // Set V0 to 2 and the sound timer to V0.
+        let mut emu = emu_with(&[0x6002, 0xF018]);
+        emu.set_audio_logging(true);
+
+        emu.tick_n(2).unwrap();
+        emu.tick_timers();
+        emu.tick_timers();
+        assert_eq!(emu.take_audio_log(), [(1, true), (2, false)]);
+        assert!(emu.take_audio_log().is_empty());
+    }
+
+    #[test]
+    fn reset_timers_stops_the_beep() {
+        let mut emu = emu_with(&[]);
+        emu.set_audio_logging(true);
+        emu.dt = 10;
+        emu.st = 10;
+        emu.tick_timers();
+        assert!(emu.is_beeping());
+
+        emu.reset_timers();
+        assert_eq!((emu.get_delay_timer(), emu.get_sound_timer()), (0, 0));
+        assert!(!emu.is_beeping());
+        assert_eq!(emu.take_audio_log(), [(0, false)]);
+        assert!(emu.tick_timers().beep_stopped);
+    }
+
+    #[test]
+    fn beep_threshold_suppresses_short_beeps() {
+        // Set the sound timer to 2, then to 10.
+        let mut emu = emu_with(&[0x6002, 0xF018, 0x600A, 0xF018]);
+        emu.set_beep_threshold(3);
+        emu.set_audio_logging(true);
+
+        emu.tick_n(2).unwrap();
+        assert!(!emu.is_beeping());
+        assert_eq!(emu.tick_timers(), TimerEvent::default());
+
+        emu.tick_n(2).unwrap();
+        assert!(emu.is_beeping());
+        assert!(emu.tick_timers().beep_started);
+        // Keeps beeping until the timer drops below the threshold.
+        let events: Vec<TimerEvent> = (0..7).map(|_| emu.tick_timers()).collect();
+        assert!(events[..6].iter().all(|event| *event == TimerEvent::default()));
+        assert!(events[6].beep_stopped);
+        assert!(!emu.is_beeping());
+        assert_eq!(emu.take_audio_log(), [(3, true), (4, false)]);
+    }
+
+    #[test]
+    fn frame_log_captures_once_per_timer_tick() {
+        // Draw the 0 glyph at x 0 and then at x 8.
+        let mut emu = emu_with(&[0xD015, 0x6008, 0xD015]);
+        emu.set_frame_logging(true);
+
+        emu.tick_n(3).unwrap();
+        emu.tick_timers();
+        emu.tick_timers();
+
+        let frames = emu.take_frame_log();
+        assert_eq!(frames.len(), 1);
+        assert!(frames[0].starts_with("####    ####  "));
+        assert!(emu.take_frame_log().is_empty());
+    }
+
+    #[test]
+    fn timers_active_with_either_timer() {
+        let mut emu = emu_with(&[]);
+        assert!(!emu.timers_active());
+
+        for (dt, st, active) in [(0, 0, false), (1, 0, true), (0, 1, true), (1, 1, true)] {
+            emu.dt = dt;
+            emu.st = st;
+            assert_eq!(emu.timers_active(), active, "dt {} st {}", dt, st);
+        }
+
+        emu.tick_timers();
+        assert!(!emu.timers_active());
+    }
+
+    #[test]
+    fn jump_with_offset_past_ram_fails() {
+        // V0 + NNN, the BXNN form stays below 0x200 and can't leave RAM.
+        let mut emu = emu_with(&[0xBFFF]);
+        emu.v_reg[0] = 0xFF;
+
+        emu.tick().unwrap();
+        assert_eq!(emu.get_pc(), 0x10FE);
+        assert_eq!(emu.tick(), Err(ExecError::PcOutOfBounds(0x10FE)));
+    }
+
+    #[test]
+    fn fill_ram_leaves_font_and_program_untouched() {
+        let program = [0x12, 0x00];
+        let mut emu = EmuBuilder::new().fill_ram(0xAA).build();
+        emu.load(&program).unwrap();
+
+        let ram = emu.get_ram();
+        assert_eq!(ram[..FONTSET_SIZE], FONTSET);
+        assert!(ram[FONTSET_SIZE..START_ADDR as usize].iter().all(|&byte| byte == 0xAA));
+        assert_eq!(ram[START_ADDR as usize..START_ADDR as usize + 2], program);
+        assert!(ram[START_ADDR as usize + 2..].iter().all(|&byte| byte == 0xAA));
+
+        // Reset fills again, the game has to be loaded anew.
+        emu.reset();
+        assert_eq!(emu.get_ram()[..FONTSET_SIZE], FONTSET);
+        assert!(emu.get_ram()[FONTSET_SIZE..].iter().all(|&byte| byte == 0xAA));
+    }
+
+    #[test]
+    fn font_protection_blocks_fx55() {
+        for protect in [true, false] {
+            // Point I at the glyph for 1 and store V0 and V1 over it.
+            let mut emu = emu_with(&[0xA005, 0x60AA, 0x61BB, 0xF155]);
+            emu.set_font_protection(protect);
+            emu.tick_n(4).unwrap();
+
+            if protect {
+                assert_eq!(emu.ram[5..7], FONTSET[5..7]);
+            } else {
+                assert_eq!(emu.ram[5..7], [0xAA, 0xBB]);
+            }
+        }
+    }
+
+    #[test]
+    fn load_store_moves_i_only_with_the_quirk() {
+        for load_store_increments_i in [false, true] {
+            let quirks = Quirks {
+                load_store_increments_i,
+                ..Quirks::default()
+            };
+            let expected = if load_store_increments_i { 0x304 } else { 0x300 };
+
+            // Store V0 - V3 and load them back from 0x300.
+            let mut emu = emu_with(&[0xA300, 0xF355, 0xA300, 0xF365]);
+            emu.set_quirks(quirks);
+            emu.tick_n(2).unwrap();
+            assert_eq!(emu.get_i(), expected);
+            emu.tick_n(2).unwrap();
+            assert_eq!(emu.get_i(), expected);
+
+            // At the top of extended memory I only wraps when it advances.
+            let mut emu = EmuBuilder::new().extended_ram(true).build();
+            emu.set_quirks(quirks);
+            emu.load(&[0xF3, 0x55]).unwrap();
+            emu.i_reg = 0xFFFE;
+            emu.tick().unwrap();
+            assert_eq!(emu.get_i(), if load_store_increments_i { 0x0002 } else { 0xFFFE });
+        }
+    }
+
+    #[test]
+    fn bcd_of_every_byte() {
+        for value in 0..=255u8 {
+            let mut emu = emu_with(&[0xA300, 0xF033]);
+            emu.v_reg[0] = value;
+            emu.tick_n(2).unwrap();
+
+            assert_eq!(emu.ram[0x300..0x303], [value / 100, value / 10 % 10, value % 10], "V0 = {}", value);
+        }
+    }
+
+    #[test]
+    fn oversized_image_is_rejected() {
+        let mut emu = Emu::with_seed(0);
+
+        assert_eq!(
+            emu.load(&[0; 4000]),
+            Err(LoadError::TooLarge {
+                len: 4000,
+                max: RAM_SIZE - START_ADDR as usize,
+            })
+        );
+    }
+
+    #[test]
+    fn program_ending_at_the_end_of_ram() {
+        // Fill all of RAM after the start address, the last instruction sets V0.
+        let mut program = vec![0; RAM_SIZE - START_ADDR as usize];
+        let last = program.len() - 2;
+        program[last..].copy_from_slice(&[0x60, 0x01]);
+        let mut emu = Emu::with_seed(0);
+        emu.load(&program).unwrap();
+        emu.pc = (RAM_SIZE - 2) as u16;
+
+        assert_eq!(emu.step(), Ok(0x6001));
+        assert_eq!(emu.step(), Err(ExecError::PcOutOfBounds(0x1000)));
+    }
+
+    // Fails to compile if a field, e.g. the trace hook, stops the emulator from moving to another thread.
+    #[test]
+    fn emu_is_send() {
+        fn assert_send<T: Send>() {}
+        assert_send::<Emu>();
+    }
+
+    #[test]
+    fn load_address_past_the_end_of_ram() {
+        let mut emu = Emu::with_seed(0);
+
+        assert_eq!(emu.load_at(&[], 0x2000), Err(LoadError::AddressOutOfRange(0x2000)));
+        assert_eq!(emu.load_at(&[0x00, 0xE0], 0x2000), Err(LoadError::AddressOutOfRange(0x2000)));
+        assert_eq!(emu.load_at(&[], RAM_SIZE as u16), Ok(()));
+    }
+
+    #[test]
+    fn drawing_a_sprite_twice_erases_it() {
+        // Draw the glyph for 0 at the same spot twice.
+        let mut emu = emu_with(&[0xD015, 0xD015]);
+        emu.tick().unwrap();
+        assert!(!lit_pixels(&emu).is_empty());
+        assert_eq!(emu.v_reg[0xF], 0);
+
+        emu.tick().unwrap();
+        assert!(lit_pixels(&emu).is_empty());
+        assert_eq!(emu.v_reg[0xF], 1);
     }
 }
@@ -0,0 +1,168 @@
+#[cfg(feature = "serde")]
+use std::fs::File;
+#[cfg(feature = "serde")]
+use std::io::{self, BufReader, BufWriter};
+#[cfg(feature = "serde")]
+use std::path::Path;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{Emu, NUM_KEYS};
+
+// Input recorded frame by frame, enough to reproduce a run exactly when replayed on an emulator created with the
+// same seed, quirks and game. Configuration isn't recorded, the frontend has to set it up the same way.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Replay {
+    // Seed the emulator was created with, see `Emu::with_seed`.
+    pub seed: u64,
+    // Key state of each frame, bit N set while key N is held.
+    pub frames: Vec<u16>,
+}
+
+impl Replay {
+    // Create an emulator seeded the same as the recorded one.
+    pub fn emu(&self) -> Emu {
+        Emu::with_seed(self.seed)
+    }
+
+    #[cfg(feature = "serde")]
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let file = BufWriter::new(File::create(path)?);
+        serde_json::to_writer(file, self)?;
+
+        Ok(())
+    }
+
+    #[cfg(feature = "serde")]
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let file = BufReader::new(File::open(path)?);
+
+        Ok(serde_json::from_reader(file)?)
+    }
+}
+
+// Builds a replay by logging the keys once per frame.
+pub struct Recorder {
+    replay: Replay,
+}
+
+impl Recorder {
+    // The emulator being recorded must have been created with this seed.
+    pub fn new(seed: u64) -> Self {
+        Self {
+            replay: Replay {
+                seed,
+                frames: Vec::new(),
+            },
+        }
+    }
+
+    // Log the current keys, call once per frame at the same point the player will feed them back, e.g. right
+    // before the frame's instructions run.
+    pub fn record(&mut self, emu: &Emu) {
+        let mask = emu
+            .get_keys()
+            .iter()
+            .enumerate()
+            .fold(0, |mask, (idx, &pressed)| mask | (pressed as u16) << idx);
+
+        self.replay.frames.push(mask);
+    }
+
+    pub fn finish(self) -> Replay {
+        self.replay
+    }
+}
+
+// Feeds a replay's keys back one frame at a time.
+pub struct Player {
+    replay: Replay,
+    frame: usize,
+}
+
+impl Player {
+    pub fn new(replay: Replay) -> Self {
+        Self { replay, frame: 0 }
+    }
+
+    // Set the keys for the next frame, returns false without changing them once the replay is over.
+    pub fn next_frame(&mut self, emu: &mut Emu) -> bool {
+        let Some(&mask) = self.replay.frames.get(self.frame) else {
+            return false;
+        };
+
+        let mut keys = [false; NUM_KEYS];
+        for (idx, key) in keys.iter_mut().enumerate() {
+            *key = mask & (1 << idx) != 0;
+        }
+
+        emu.set_keys(keys);
+        self.frame += 1;
+
+        true
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.frame >= self.replay.frames.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Draws random numbers and counts the frames key 5 was held in V2.
+    const PROGRAM: [u8; 10] = [0xC0, 0xFF, 0x61, 0x05, 0xE1, 0xA1, 0x72, 0x01, 0x12, 0x00];
+
+    fn run_frame(emu: &mut Emu) {
+        emu.tick_n(10).unwrap();
+        emu.tick_timers();
+    }
+
+    #[test]
+    fn replay_reproduces_the_run() {
+        let mut emu = Emu::with_seed(3);
+        emu.load(&PROGRAM).unwrap();
+        let mut recorder = Recorder::new(3);
+
+        for frame in 0..20 {
+            emu.keypress(5, frame % 3 == 0);
+            emu.keypress(0xA, frame % 7 == 0);
+            recorder.record(&emu);
+            run_frame(&mut emu);
+        }
+
+        let replay = recorder.finish();
+        assert_eq!(replay.frames.len(), 20);
+        assert_eq!(replay.frames[0], 1 << 5 | 1 << 0xA);
+
+        let mut replayed = replay.emu();
+        replayed.load(&PROGRAM).unwrap();
+        let mut player = Player::new(replay);
+        while player.next_frame(&mut replayed) {
+            run_frame(&mut replayed);
+        }
+
+        assert!(player.is_finished());
+        assert!(!player.next_frame(&mut replayed));
+        assert_eq!(replayed.save_state(), emu.save_state());
+        assert_ne!(emu.get_registers()[2], 0);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn save_and_load() {
+        let replay = Replay {
+            seed: 11,
+            frames: vec![0, 1, 0x8000, 0xFFFF],
+        };
+        let path = std::env::temp_dir().join(format!("chip8-replay-{}.json", std::process::id()));
+
+        replay.save(&path).unwrap();
+        let loaded = Replay::load(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.unwrap(), replay);
+    }
+}
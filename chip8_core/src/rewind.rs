@@ -0,0 +1,104 @@
+use std::collections::VecDeque;
+
+use crate::state::SaveState;
+use crate::{Emu, TimerEvent};
+
+// Default history length, 10 seconds at 60 frames per second.
+pub const DEFAULT_REWIND_FRAMES: usize = 600;
+
+// Wraps an emulator and snapshots its state at the end of every frame so gameplay can be rewound.
+// Each snapshot holds a full copy of RAM and the screen, about 12KB with standard RAM or 72KB with extended RAM,
+// so the default 600 frames cost around 7MB.
+pub struct RewindableEmu {
+    emu: Emu,
+    history: VecDeque<SaveState>,
+    capacity: usize,
+}
+
+impl RewindableEmu {
+    // Keep up to capacity frames of history, the oldest are dropped first.
+    pub fn new(emu: Emu, capacity: usize) -> Self {
+        Self {
+            emu,
+            history: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    pub fn emu(&self) -> &Emu {
+        &self.emu
+    }
+
+    // Direct access for loading, input and ticking. Changes are only recorded at the next tick_timers.
+    pub fn emu_mut(&mut self) -> &mut Emu {
+        &mut self.emu
+    }
+
+    // Tick the timers, ending the frame, and record a snapshot of it.
+    pub fn tick_timers(&mut self) -> TimerEvent {
+        let event = self.emu.tick_timers();
+
+        if self.capacity > 0 {
+            if self.history.len() == self.capacity {
+                self.history.pop_front();
+            }
+
+            self.history.push_back(self.emu.save_state());
+        }
+
+        event
+    }
+
+    // Go back to the end of the frame the given number of frames before the last recorded one, or the oldest
+    // recorded frame if there isn't that much history. Rewound frames are dropped, so play continues from there.
+    // Returns the number of frames actually rewound.
+    pub fn rewind(&mut self, frames: usize) -> usize {
+        let frames = frames.min(self.history.len().saturating_sub(1));
+        self.history.truncate(self.history.len() - frames);
+
+        if let Some(state) = self.history.back() {
+            // Only states saved by the emulator itself are recorded, so they always load.
+            self.emu.load_state(state.clone()).unwrap();
+        }
+
+        frames
+    }
+
+    // Drop all recorded frames, e.g. after loading a new game.
+    pub fn clear_history(&mut self) {
+        self.history.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rewind_restores_earlier_frames() {
+        // Add 1 to V0 forever.
+        let mut emu = Emu::with_seed(0);
+        emu.load(&[0x70, 0x01, 0x12, 0x00]).unwrap();
+        let mut rewindable = RewindableEmu::new(emu, 3);
+
+        let mut frames = Vec::new();
+        for _ in 0..5 {
+            rewindable.emu_mut().tick_n(10).unwrap();
+            rewindable.tick_timers();
+            frames.push(rewindable.emu().save_state());
+        }
+
+        assert_eq!(rewindable.rewind(1), 1);
+        assert_eq!(rewindable.emu().save_state(), frames[3]);
+
+        // Only three frames were kept, one of which was just rewound.
+        assert_eq!(rewindable.rewind(5), 1);
+        assert_eq!(rewindable.emu().save_state(), frames[2]);
+        assert_eq!(rewindable.emu().get_registers()[0], 15);
+
+        // Play continues from the rewound frame.
+        rewindable.emu_mut().tick_n(10).unwrap();
+        rewindable.tick_timers();
+        assert_eq!(rewindable.emu().save_state(), frames[3]);
+    }
+}
@@ -0,0 +1,352 @@
+use std::fmt;
+
+use rand::SeedableRng;
+use rand_chacha::ChaCha12Rng;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "serde")]
+use serde_big_array::BigArray;
+
+use crate::{
+    Emu, ExecError, AUDIO_BUFFER_SIZE, HIRES_SCREEN_HEIGHT, NUM_KEYS, NUM_PLANES, NUM_REGS, NUM_RPL_FLAGS, RAM_SIZE,
+    XO_RAM_SIZE,
+};
+
+// Snapshot of the full machine state, used for quicksave and quickload.
+// Configuration like quirks isn't part of the machine state and is not included.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SaveState {
+    pub pc: u16,
+    pub ram: Vec<u8>,
+    #[cfg_attr(feature = "serde", serde(with = "BigArray"))]
+    pub screen: [[u128; NUM_PLANES]; HIRES_SCREEN_HEIGHT],
+    pub planes: u8,
+    pub hires: bool,
+    pub v_reg: [u8; NUM_REGS],
+    pub rpl_flags: [u8; NUM_RPL_FLAGS],
+    pub i_reg: u16,
+    pub sp: u16,
+    pub stack: Vec<u16>,
+    pub keys: [bool; NUM_KEYS],
+    pub dt: u8,
+    pub st: u8,
+    pub audio_buffer: [u8; AUDIO_BUFFER_SIZE],
+    pub pitch: u8,
+    pub halted: bool,
+    // Seed and position of the CXNN random number generator, so a reloaded state draws the same numbers.
+    pub rng_seed: [u8; 32],
+    pub rng_word_pos: u128,
+}
+
+// Everything needed to reproduce a fault, for attaching to a bug report. Create it with Emu::reproducer right
+// after tick or tick_n returned the error.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Reproducer {
+    // The game as it was loaded, before it could modify itself.
+    pub rom: Vec<u8>,
+    // Machine state at the fault.
+    pub state: SaveState,
+    // Instructions executed since the last reset.
+    pub cycles: u64,
+    pub fault: ExecError,
+}
+
+impl Reproducer {
+    // Create an emulator in the state of the fault. Configuration like quirks starts from the defaults.
+    pub fn emu(&self) -> Result<Emu, StateError> {
+        let mut emu = Emu::new();
+        emu.load_state(self.state.clone())?;
+        emu.cycles = self.cycles;
+
+        Ok(emu)
+    }
+}
+
+// Reasons a save state can't be loaded, e.g. a hand-edited or corrupted file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum StateError {
+    // RAM is neither the standard nor the extended size.
+    RamSize(usize),
+    // The stack pointer is past the end of the stack.
+    StackPointer { sp: u16, len: usize },
+}
+
+impl fmt::Display for StateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StateError::RamSize(len) => write!(f, "invalid RAM size of {} bytes", len),
+            StateError::StackPointer { sp, len } => {
+                write!(f, "stack pointer {} is past the end of the {} entry stack", sp, len)
+            }
+        }
+    }
+}
+
+impl std::error::Error for StateError {}
+
+// Format version written by Emu::snapshot, bumped whenever SaveState changes incompatibly.
+#[cfg(feature = "serde")]
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+// A serialized snapshot, the save state tagged with the format version.
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+struct Snapshot {
+    version: u32,
+    state: SaveState,
+}
+
+// Only the version, read first so a snapshot from another version is reported as such rather than as unreadable.
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+struct SnapshotVersion {
+    version: u32,
+}
+
+// Reasons a serialized snapshot can't be restored.
+#[cfg(feature = "serde")]
+#[derive(Debug)]
+pub enum SnapshotError {
+    // The bytes aren't a snapshot at all.
+    Parse(serde_json::Error),
+    // The snapshot was written with another format version.
+    Version(u32),
+    State(StateError),
+}
+
+#[cfg(feature = "serde")]
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SnapshotError::Parse(err) => write!(f, "unreadable snapshot: {}", err),
+            SnapshotError::Version(version) => write!(
+                f,
+                "snapshot version {} is not supported, expected {}",
+                version, SNAPSHOT_VERSION
+            ),
+            SnapshotError::State(err) => err.fmt(f),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl std::error::Error for SnapshotError {}
+
+impl Emu {
+    pub fn save_state(&self) -> SaveState {
+        SaveState {
+            pc: self.pc,
+            ram: self.ram.clone(),
+            screen: self.screen,
+            planes: self.planes,
+            hires: self.hires,
+            v_reg: self.v_reg,
+            rpl_flags: self.rpl_flags,
+            i_reg: self.i_reg,
+            sp: self.sp,
+            stack: self.stack.clone(),
+            keys: self.keys,
+            dt: self.dt,
+            st: self.st,
+            audio_buffer: self.audio_buffer,
+            pitch: self.pitch,
+            halted: self.halted,
+            rng_seed: self.rng.get_seed(),
+            rng_word_pos: self.rng.get_word_pos(),
+        }
+    }
+
+    // Restore a snapshot, the stack and RAM take the size they had when saved.
+    // Fails without changing anything if the state couldn't have been saved by an emulator.
+    pub fn load_state(&mut self, state: SaveState) -> Result<(), StateError> {
+        if state.ram.len() != RAM_SIZE && state.ram.len() != XO_RAM_SIZE {
+            return Err(StateError::RamSize(state.ram.len()));
+        }
+
+        if state.sp as usize > state.stack.len() {
+            return Err(StateError::StackPointer {
+                sp: state.sp,
+                len: state.stack.len(),
+            });
+        }
+
+        self.pc = state.pc;
+        self.ram = state.ram;
+        self.screen = state.screen;
+        self.planes = state.planes;
+        self.hires = state.hires;
+        self.v_reg = state.v_reg;
+        self.rpl_flags = state.rpl_flags;
+        self.i_reg = state.i_reg;
+        self.sp = state.sp;
+        self.stack = state.stack;
+        self.keys = state.keys;
+        self.dt = state.dt;
+        self.st = state.st;
+        self.audio_buffer = state.audio_buffer;
+        self.pitch = state.pitch;
+        self.halted = state.halted;
+        self.rng = ChaCha12Rng::from_seed(state.rng_seed);
+        self.rng.set_word_pos(state.rng_word_pos);
+        self.mark_display_changed();
+        self.vblank_pending = false;
+
+        Ok(())
+    }
+
+    // Bundle the current state with the game and the fault it stopped on. The emulator doesn't keep the game
+    // around, so the caller passes in what it loaded.
+    pub fn reproducer(&self, rom: &[u8], fault: ExecError) -> Reproducer {
+        Reproducer {
+            rom: rom.to_vec(),
+            state: self.save_state(),
+            cycles: self.cycles,
+            fault,
+        }
+    }
+
+    // Serialize the machine state as a versioned snapshot, e.g. for a save file. Read back with from_snapshot.
+    #[cfg(feature = "serde")]
+    pub fn snapshot(&self) -> Vec<u8> {
+        let snapshot = Snapshot {
+            version: SNAPSHOT_VERSION,
+            state: self.save_state(),
+        };
+
+        // Can't fail, the state only holds numbers, arrays and vectors.
+        serde_json::to_vec(&snapshot).unwrap()
+    }
+
+    // Create an emulator from a snapshot, rejecting snapshots of another version. The RAM and stack take the
+    // sizes they had when saved, other configuration like quirks starts from the defaults.
+    #[cfg(feature = "serde")]
+    pub fn from_snapshot(bytes: &[u8]) -> Result<Emu, SnapshotError> {
+        let version: SnapshotVersion = serde_json::from_slice(bytes).map_err(SnapshotError::Parse)?;
+        if version.version != SNAPSHOT_VERSION {
+            return Err(SnapshotError::Version(version.version));
+        }
+
+        let snapshot: Snapshot = serde_json::from_slice(bytes).map_err(SnapshotError::Parse)?;
+        let mut emu = Emu::new();
+        emu.load_state(snapshot.state).map_err(SnapshotError::State)?;
+
+        Ok(emu)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip_reproduces_random_numbers() {
+        // Fill V0 - V3 with random bytes, then call a subroutine that does nothing.
+        let program = [0xC0, 0xFF, 0xC1, 0xFF, 0xC2, 0xFF, 0xC3, 0xFF, 0x22, 0x0C, 0x00, 0x00, 0x00, 0xEE];
+        let mut emu = Emu::with_seed(7);
+        emu.load(&program).unwrap();
+        emu.tick_n(2).unwrap();
+        emu.keypress(5, true);
+        emu.dt = 30;
+
+        let state = emu.save_state();
+        emu.tick_n(3).unwrap();
+        let after = emu.save_state();
+
+        let mut other = Emu::with_seed(99);
+        other.load_state(state.clone()).unwrap();
+        assert_eq!(other.save_state(), state);
+        other.tick_n(3).unwrap();
+        assert_eq!(other.save_state(), after);
+        assert_eq!(other.get_sp(), 1);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn round_trip_through_json() {
+        let mut emu = Emu::with_seed(1);
+        emu.load(&[0xC0, 0xFF, 0xD0, 0x05]).unwrap();
+        emu.tick_n(2).unwrap();
+
+        let state = emu.save_state();
+        let json = serde_json::to_string(&state).unwrap();
+        assert_eq!(serde_json::from_str::<SaveState>(&json).unwrap(), state);
+    }
+
+    #[test]
+    fn invalid_states_are_rejected() {
+        let mut emu = Emu::new();
+        let valid = emu.save_state();
+
+        let mut state = valid.clone();
+        state.sp = state.stack.len() as u16 + 1;
+        assert_eq!(
+            emu.load_state(state),
+            Err(StateError::StackPointer { sp: 17, len: 16 })
+        );
+
+        let mut state = valid.clone();
+        state.ram.clear();
+        assert_eq!(emu.load_state(state), Err(StateError::RamSize(0)));
+
+        assert_eq!(emu.save_state(), valid);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn fresh_emulator_from_a_snapshot() {
+        // Draw a random sprite in a loop.
+        let program = [0xC0, 0xFF, 0xD0, 0x05, 0x12, 0x00];
+        let mut emu = crate::EmuBuilder::new().seed(3).extended_ram(true).stack_size(4).build();
+        emu.load(&program).unwrap();
+        emu.tick_n(7).unwrap();
+
+        let mut restored = Emu::from_snapshot(&emu.snapshot()).unwrap();
+        assert_eq!(restored.save_state(), emu.save_state());
+        emu.tick_n(9).unwrap();
+        restored.tick_n(9).unwrap();
+        assert_eq!(restored.save_state(), emu.save_state());
+        assert_eq!(restored.get_display(), emu.get_display());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn incompatible_snapshots_are_rejected() {
+        let bytes = Emu::new().snapshot();
+        let json = String::from_utf8(bytes).unwrap();
+        let other_version = json.replacen("\"version\":1", "\"version\":2", 1);
+
+        assert!(matches!(
+            Emu::from_snapshot(other_version.as_bytes()),
+            Err(SnapshotError::Version(2))
+        ));
+        assert!(matches!(Emu::from_snapshot(b"{}"), Err(SnapshotError::Parse(_))));
+    }
+
+    #[test]
+    fn reproducer_from_a_fault() {
+        // Set V0, then return without a call.
+        let program = [0x60, 0x2A, 0x00, 0xEE];
+        let mut emu = Emu::with_seed(0);
+        emu.load(&program).unwrap();
+        let fault = emu.tick_n(2).unwrap_err();
+        assert_eq!(fault, ExecError::StackUnderflow);
+
+        let reproducer = emu.reproducer(&program, fault);
+        assert_eq!(reproducer.rom, program);
+        assert_eq!(reproducer.state, emu.save_state());
+        assert_eq!(reproducer.cycles, emu.cycles());
+        assert_eq!(reproducer.fault, ExecError::StackUnderflow);
+
+        let restored = reproducer.emu().unwrap();
+        assert_eq!(restored.get_registers()[0], 0x2A);
+        assert_eq!(restored.cycles(), emu.cycles());
+
+        #[cfg(feature = "serde")]
+        {
+            let json = serde_json::to_string(&reproducer).unwrap();
+            assert_eq!(serde_json::from_str::<Reproducer>(&json).unwrap(), reproducer);
+        }
+    }
+}
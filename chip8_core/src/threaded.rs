@@ -0,0 +1,224 @@
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use crate::{Emu, EmuBuilder, LoadError, UnknownOpcodes, NUM_KEYS, RAM_SIZE, START_ADDR, XO_RAM_SIZE};
+
+// Timers run at 60Hz, so the thread runs one batch of instructions per 1/60th of a second.
+const FRAME_TIME: Duration = Duration::from_micros(16_667);
+const TICKS_PER_FRAME: usize = 10;
+
+enum Command {
+    Load(Vec<u8>),
+    Keypress(usize, bool),
+    Pause,
+    Resume,
+    Step,
+    Reset,
+    Quit,
+}
+
+// Runs an emulator on its own thread so a GUI event loop never blocks on emulation.
+// The thread pauses itself if execution fails.
+// Commands are sent over one channel and a copy of the screen comes back over another whenever it changes.
+// Frames queue up until read, so callers should drain them regularly.
+pub struct EmuHandle {
+    commands: Sender<Command>,
+    frames: Receiver<Vec<u8>>,
+    thread: Option<JoinHandle<()>>,
+    // Largest game that fits in the thread's emulator.
+    max_rom_size: usize,
+}
+
+impl EmuHandle {
+    // Start the emulator thread with the default configuration, running at 60 frames per second.
+    pub fn spawn() -> Self {
+        Self::spawn_with(EmuBuilder::new())
+    }
+
+    // Start the emulator thread with an emulator built from the given builder, e.g. to set quirks or extended RAM.
+    // Unknown opcodes stop the game like any other error instead of panicking the thread.
+    // Loading a game resets the emulator, which keeps this configuration but starts in low resolution.
+    pub fn spawn_with(builder: EmuBuilder) -> Self {
+        let ram_size = if builder.extended_ram { XO_RAM_SIZE } else { RAM_SIZE };
+        let (commands, command_rx) = mpsc::channel();
+        let (frame_tx, frames) = mpsc::channel();
+        let thread = thread::spawn(move || {
+            let mut emu = builder.build();
+            emu.set_unknown_opcodes(UnknownOpcodes::Error);
+            run(emu, command_rx, frame_tx)
+        });
+
+        Self {
+            commands,
+            frames,
+            thread: Some(thread),
+            max_rom_size: ram_size - START_ADDR as usize,
+        }
+    }
+
+    // Reset the emulator and load a new game, the size is checked up front since loading happens on the thread.
+    pub fn load(&self, data: &[u8]) -> Result<(), LoadError> {
+        if data.len() > self.max_rom_size {
+            return Err(LoadError::TooLarge {
+                len: data.len(),
+                max: self.max_rom_size,
+            });
+        }
+
+        self.send(Command::Load(data.to_vec()));
+
+        Ok(())
+    }
+
+    // Panics here rather than on the emulator thread if idx isn't one of the 16 keys.
+    pub fn keypress(&self, idx: usize, pressed: bool) {
+        assert!(idx < NUM_KEYS, "key index {} out of range", idx);
+        self.send(Command::Keypress(idx, pressed));
+    }
+
+    pub fn pause(&self) {
+        self.send(Command::Pause);
+    }
+
+    pub fn resume(&self) {
+        self.send(Command::Resume);
+    }
+
+    // Execute a single instruction, meant to be used while paused.
+    pub fn step(&self) {
+        self.send(Command::Step);
+    }
+
+    pub fn reset(&self) {
+        self.send(Command::Reset);
+    }
+
+    // Return the most recent frame without blocking, dropping any older queued frames.
+    pub fn latest_frame(&self) -> Option<Vec<u8>> {
+        self.frames.try_iter().last()
+    }
+
+    // Block until the next frame arrives, or return None if it doesn't arrive in time.
+    pub fn wait_frame(&self, timeout: Duration) -> Option<Vec<u8>> {
+        self.frames.recv_timeout(timeout).ok()
+    }
+
+    // Sending only fails once the thread is gone, in which case there is nothing left to control.
+    fn send(&self, command: Command) {
+        let _ = self.commands.send(command);
+    }
+}
+
+impl Drop for EmuHandle {
+    fn drop(&mut self) {
+        self.send(Command::Quit);
+
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+// Emulator thread main loop.
+fn run(mut emu: Emu, commands: Receiver<Command>, frames: Sender<Vec<u8>>) {
+    let mut paused = false;
+    let mut next_frame = Instant::now() + FRAME_TIME;
+
+    loop {
+        // Handle commands until the next frame is due, or indefinitely while paused.
+        let command = if paused {
+            commands.recv().map_err(|_| RecvTimeoutError::Disconnected)
+        } else {
+            commands.recv_timeout(next_frame.saturating_duration_since(Instant::now()))
+        };
+
+        match command {
+            Ok(Command::Load(data)) => {
+                emu.reset();
+                // Already checked by EmuHandle::load.
+                let _ = emu.load(&data);
+            }
+            Ok(Command::Keypress(idx, pressed)) => emu.keypress(idx, pressed),
+            Ok(Command::Pause) => paused = true,
+            Ok(Command::Resume) => {
+                paused = false;
+                next_frame = Instant::now() + FRAME_TIME;
+            }
+            // Execution can't continue after an error, so pause until resumed, usually after a load or reset.
+            Ok(Command::Step) => paused |= emu.tick().is_err(),
+            Ok(Command::Reset) => emu.reset(),
+            Ok(Command::Quit) | Err(RecvTimeoutError::Disconnected) => return,
+
+            // The frame is due.
+            Err(RecvTimeoutError::Timeout) => {
+                for _ in 0..TICKS_PER_FRAME {
+                    if emu.tick().is_err() {
+                        paused = true;
+                        break;
+                    }
+                }
+
+                emu.tick_timers();
+                next_frame += FRAME_TIME;
+            }
+        }
+
+        if emu.take_display_changed() && frames.send(emu.get_display()).is_err() {
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Quirks, SCREEN_HEIGHT, SCREEN_WIDTH};
+
+    // Point I at the font sprite for 0 and draw it at V0, V1.
+    const DRAW_ZERO: [u8; 4] = [0xA0, 0x00, 0xD0, 0x15];
+
+    // First frame with something drawn, loading also sends the cleared screen.
+    fn first_drawn_frame(handle: &EmuHandle) -> Vec<u8> {
+        loop {
+            let frame = handle.wait_frame(Duration::from_secs(5)).unwrap();
+            if frame.contains(&1) {
+                return frame;
+            }
+        }
+    }
+
+    #[test]
+    fn spawned_handle_delivers_frames() {
+        let handle = EmuHandle::spawn();
+        handle.load(&DRAW_ZERO).unwrap();
+
+        let frame = first_drawn_frame(&handle);
+        assert_eq!(frame.len(), SCREEN_WIDTH * SCREEN_HEIGHT);
+        // The top row of the 0 glyph.
+        assert_eq!(frame[..5], [1, 1, 1, 1, 0]);
+    }
+
+    #[test]
+    fn spawn_with_a_configured_builder() {
+        assert!(EmuHandle::spawn().load(&[0; RAM_SIZE]).is_err());
+
+        let quirks = Quirks {
+            wrap_x: false,
+            ..Quirks::default()
+        };
+        let handle = EmuHandle::spawn_with(EmuBuilder::new().extended_ram(true).quirks(quirks));
+        handle.load(&[0; RAM_SIZE]).unwrap();
+        // Set V0 to 62 and draw a 0 clipped at the right edge.
+        handle.load(&[[0x60, 62].as_slice(), &DRAW_ZERO].concat()).unwrap();
+
+        let frame = first_drawn_frame(&handle);
+        assert_eq!(frame[..SCREEN_WIDTH], [[0; 62].as_slice(), &[1, 1]].concat());
+    }
+
+    #[test]
+    #[should_panic(expected = "key index 16 out of range")]
+    fn keypress_checks_the_index() {
+        EmuHandle::spawn().keypress(16, true);
+    }
+}
@@ -0,0 +1,60 @@
+use std::time::Duration;
+
+// Longest frame taken into account, so a long stall (e.g. dragging the window) doesn't cause a burst afterwards.
+// Anything longer counts as this long.
+pub const MAX_ELAPSED: Duration = Duration::from_millis(250);
+
+// Adaptive alternative to a fixed number of ticks per frame.
+// Converts measured frame times into the number of instructions needed to hit a target instruction rate.
+// Fractional instructions carry over to the next frame, so over time the executed count tracks the target
+// even when frames are uneven.
+pub struct Pacer {
+    hz: u32,
+    carry: f64,
+}
+
+impl Pacer {
+    // Create a pacer targeting the given number of instructions per second.
+    pub fn new(hz: u32) -> Self {
+        Self { hz, carry: 0.0 }
+    }
+
+    // Return how many instructions to execute for a frame that took `elapsed`.
+    pub fn budget(&mut self, elapsed: Duration) -> usize {
+        let due = elapsed.min(MAX_ELAPSED).as_secs_f64() * self.hz as f64 + self.carry;
+        let count = due.floor();
+        self.carry = due - count;
+
+        count as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A 128th of a second, exact in floating point so budgets don't depend on rounding.
+    const UNIT: Duration = Duration::from_nanos(7_812_500);
+
+    #[test]
+    fn uneven_frames_track_the_target_rate() {
+        let mut pacer = Pacer::new(64);
+        let frames = [1, 3, 2, 5, 1, 4];
+
+        let budgets: Vec<usize> = frames.iter().map(|&units| pacer.budget(UNIT * units)).collect();
+
+        // Half an instruction per unit, with the halves carried over.
+        assert_eq!(budgets, [0, 2, 1, 2, 1, 2]);
+        assert_eq!(budgets.iter().sum::<usize>(), frames.iter().sum::<u32>() as usize / 2);
+    }
+
+    #[test]
+    fn long_frames_are_clamped_and_keep_the_carry() {
+        let mut pacer = Pacer::new(64);
+
+        assert_eq!(pacer.budget(UNIT), 0);
+        // 250ms at 64Hz, however long the stall.
+        assert_eq!(pacer.budget(Duration::from_secs(10)), 16);
+        assert_eq!(pacer.budget(UNIT), 1);
+    }
+}
@@ -0,0 +1,41 @@
+// Helpers for editors and overlays placing sprites, DXYN itself always draws from the top-left corner.
+
+// Width of every DXYN sprite in pixels, one byte per row.
+pub const SPRITE_WIDTH: usize = 8;
+
+// Return the (width, height) in pixels of a sprite with the given number of rows.
+pub fn sprite_bounds(rows: usize) -> (usize, usize) {
+    (SPRITE_WIDTH, rows)
+}
+
+// Return the top-left coordinate to draw a sprite at so that it is centered on (x, y).
+// Wraps around the edges of a screen of the given (width, height) the same way DXYN does, pass
+// `Emu::get_screen_size` so high resolution is handled.
+pub fn centered_origin(x: usize, y: usize, rows: usize, screen: (usize, usize)) -> (usize, usize) {
+    let (screen_width, screen_height) = screen;
+    let (width, height) = sprite_bounds(rows);
+    let left = (x + screen_width - (width / 2) % screen_width) % screen_width;
+    let top = (y + screen_height - (height / 2) % screen_height) % screen_height;
+
+    (left, top)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{HIRES_SCREEN_HEIGHT, HIRES_SCREEN_WIDTH, SCREEN_HEIGHT, SCREEN_WIDTH};
+
+    #[test]
+    fn centered_origin_in_both_resolutions() {
+        let lores = (SCREEN_WIDTH, SCREEN_HEIGHT);
+        let hires = (HIRES_SCREEN_WIDTH, HIRES_SCREEN_HEIGHT);
+
+        assert_eq!(sprite_bounds(5), (8, 5));
+        assert_eq!(centered_origin(32, 16, 4, lores), (28, 14));
+        assert_eq!(centered_origin(32, 16, 4, hires), (28, 14));
+
+        // Near the top-left corner the origin wraps to the far edges of the active screen.
+        assert_eq!(centered_origin(0, 0, 4, lores), (60, 30));
+        assert_eq!(centered_origin(0, 0, 4, hires), (124, 62));
+    }
+}
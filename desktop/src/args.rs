@@ -0,0 +1,318 @@
+use std::str::FromStr;
+
+use chip8_core::compat::Profile;
+
+use crate::audio::Waveform;
+use sdl2::pixels::Color;
+
+pub const USAGE: &str = "\
+Usage: cargo run path/to/game [options]
+
+Options:
+  --pixel-shape square|circle  Shape of lit pixels (default square)
+  --profile NAME               Quirks of chip8, chip48, superchip or xochip, or auto to guess from the game,
+                               F6 cycles through them while playing
+  --scale N                    Window pixels per Chip-8 pixel (default 15)
+  --ipf N, --speed N           Instructions per frame (default 10)
+  --ips N                      Target instructions per second, replaces --ipf
+  --turbo N                    Speed multiplier while Tab is held (default 4)
+  --interlace                  Blend consecutive frames to reduce flicker
+  --ghosting                   Fade pixels out over a few frames like phosphor persistence
+  --crt                        Leave dark gaps between pixel rows like CRT scanlines
+  --skip-unknown               Skip unknown opcodes instead of stopping, F3 shows how many were skipped
+  --save-faults                Save a reproducer for bug reports when the game stops on an error
+  --no-vsync                   Draw as fast as possible instead of once per monitor refresh
+  --volume 0-100               Beep volume (default 25)
+  --beep-hz N                  Beep frequency from 20 to 20000 Hz (default 440)
+  --beep-wave NAME             Beep waveform, square, sine or triangle (default square)
+  --record-scale N             GIF recording pixels per Chip-8 pixel (default 1)
+  --dump-start ADDR            Hex address the memory viewer starts at (default 200)
+  --keymap PATH                Keymap file with `key = 0xN` and `pad:button = 0xN` lines overriding the defaults
+  --fg RRGGBB                  Color of lit pixels as a hex triplet (default FFFFFF)
+  --bg RRGGBB                  Background color as a hex triplet (default 000000)";
+
+const DEFAULT_SCALE: u32 = 15;
+const DEFAULT_IPF: usize = 10;
+const DEFAULT_TURBO: usize = 4;
+const DEFAULT_BEEP_HZ: u32 = 440;
+// Roughly the range of human hearing.
+const BEEP_HZ_RANGE: std::ops::RangeInclusive<u32> = 20..=20_000;
+
+// How lit pixels are drawn on the canvas.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum PixelShape {
+    Square,
+    Circle,
+}
+
+// A quirk profile given on the command line.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum ProfileChoice {
+    Fixed(Profile),
+    // Guess from each game's instructions when it's loaded.
+    Auto,
+}
+
+// Screen colors, e.g. green or amber for a phosphor look.
+pub struct Palette {
+    pub fg: Color,
+    pub bg: Color,
+}
+
+impl Palette {
+    // Color for a pixel's color index. XO-CHIP pixels lit in the second plane or both planes get fixed greys,
+    // plain Chip-8 games only use the background and foreground.
+    pub fn color(&self, pixel: u8) -> Color {
+        match pixel {
+            0 => self.bg,
+            1 => self.fg,
+            2 => Color::RGB(170, 170, 170),
+            _ => Color::RGB(85, 85, 85),
+        }
+    }
+}
+
+// Options parsed from the command line.
+pub struct Options {
+    pub rom_path: String,
+    pub pixel_shape: PixelShape,
+    // Quirk profile to apply, None keeps the default quirks.
+    pub profile: Option<ProfileChoice>,
+    // Window pixels per Chip-8 pixel in low resolution mode, for the initial window size and screenshots.
+    pub scale: u32,
+    // Instructions executed per frame.
+    pub ipf: usize,
+    // Target instructions per second, replaces the fixed instructions per frame when set.
+    pub ips: Option<u32>,
+    // Instructions per frame are multiplied by this while the turbo key is held.
+    pub turbo: usize,
+    // Blend consecutive frames to reduce flicker.
+    pub interlace: bool,
+    // Fade pixels out instead of turning them off at once.
+    pub ghosting: bool,
+    // Draw pixel rows with a gap below them in the background color.
+    pub crt: bool,
+    // Treat unknown opcodes as no-ops instead of stopping the game.
+    pub skip_unknown: bool,
+    // Write the game, state and error to a file when emulation stops on an error.
+    pub save_faults: bool,
+    // Wait for the monitor refresh when presenting, the game's speed doesn't depend on it either way.
+    pub vsync: bool,
+    // Beep volume from 0.0 to 1.0.
+    pub volume: f32,
+    pub beep_hz: u32,
+    pub beep_wave: Waveform,
+    pub palette: Palette,
+    // GIF recording pixels per Chip-8 pixel in low resolution mode.
+    pub record_scale: u32,
+    pub keymap_path: Option<String>,
+    // First address shown by the memory viewer.
+    pub dump_start: u16,
+}
+
+// Parse the command line arguments, skipping the program name.
+pub fn parse(args: &[String]) -> Result<Options, String> {
+    let mut rom_path = None;
+    let mut pixel_shape = PixelShape::Square;
+    let mut profile = None;
+    let mut scale = DEFAULT_SCALE;
+    let mut ipf = DEFAULT_IPF;
+    let mut ips = None;
+    let mut turbo = DEFAULT_TURBO;
+    let mut interlace = false;
+    let mut ghosting = false;
+    let mut crt = false;
+    let mut vsync = true;
+    let mut skip_unknown = false;
+    let mut save_faults = false;
+    let mut volume = 0.25;
+    let mut beep_hz = DEFAULT_BEEP_HZ;
+    let mut beep_wave = Waveform::Square;
+    let mut record_scale = 1;
+    let mut keymap_path = None;
+    let mut dump_start = 0x200;
+    let mut palette = Palette {
+        fg: Color::RGB(255, 255, 255),
+        bg: Color::RGB(0, 0, 0),
+    };
+    let mut iter = args.iter().skip(1);
+
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--pixel-shape" => {
+                pixel_shape = match value(&mut iter, arg)?.as_str() {
+                    "square" => PixelShape::Square,
+                    "circle" => PixelShape::Circle,
+                    other => return Err(format!("Invalid pixel shape: {}", other)),
+                };
+            }
+
+            "--profile" => {
+                profile = Some(match value(&mut iter, arg)?.as_str() {
+                    "chip8" => ProfileChoice::Fixed(Profile::Chip8),
+                    "chip48" => ProfileChoice::Fixed(Profile::Chip48),
+                    "superchip" => ProfileChoice::Fixed(Profile::SuperChip),
+                    "xochip" => ProfileChoice::Fixed(Profile::XoChip),
+                    "auto" => ProfileChoice::Auto,
+                    other => return Err(format!("Invalid profile: {}", other)),
+                });
+            }
+
+            "--scale" => {
+                scale = number(&mut iter, arg)?;
+
+                if scale == 0 {
+                    return Err("Scale must be greater than 0".to_string());
+                }
+            }
+
+            "--ipf" | "--speed" => ipf = number(&mut iter, arg)?,
+
+            "--ips" => {
+                let value: u32 = number(&mut iter, arg)?;
+
+                if value == 0 {
+                    return Err("Instructions per second must be greater than 0".to_string());
+                }
+
+                ips = Some(value);
+            }
+
+            "--turbo" => {
+                turbo = number(&mut iter, arg)?;
+
+                if turbo == 0 {
+                    return Err("Turbo factor must be greater than 0".to_string());
+                }
+            }
+
+            "--interlace" => interlace = true,
+
+            "--ghosting" => ghosting = true,
+
+            "--crt" => crt = true,
+
+            "--no-vsync" => vsync = false,
+
+            "--skip-unknown" => skip_unknown = true,
+
+            "--save-faults" => save_faults = true,
+
+            "--volume" => {
+                let value: u8 = number(&mut iter, arg)?;
+
+                if value > 100 {
+                    return Err("Volume must be between 0 and 100".to_string());
+                }
+
+                volume = value as f32 / 100.0;
+            }
+
+            "--beep-hz" => {
+                beep_hz = number(&mut iter, arg)?;
+
+                if !BEEP_HZ_RANGE.contains(&beep_hz) {
+                    return Err("Beep frequency must be between 20 and 20000 Hz".to_string());
+                }
+            }
+
+            "--beep-wave" => {
+                beep_wave = match value(&mut iter, arg)?.as_str() {
+                    "square" => Waveform::Square,
+                    "sine" => Waveform::Sine,
+                    "triangle" => Waveform::Triangle,
+                    other => return Err(format!("Invalid waveform: {}", other)),
+                };
+            }
+
+            "--record-scale" => {
+                record_scale = number(&mut iter, arg)?;
+
+                if record_scale == 0 {
+                    return Err("Recording scale must be greater than 0".to_string());
+                }
+            }
+
+            "--dump-start" => {
+                let raw = value(&mut iter, arg)?;
+
+                dump_start = u16::from_str_radix(raw.trim_start_matches("0x"), 16)
+                    .map_err(|_| format!("Invalid address for {}: {}", arg, raw))?;
+            }
+
+            "--keymap" => keymap_path = Some(value(&mut iter, arg)?.clone()),
+
+            "--fg" => palette.fg = color(&mut iter, arg)?,
+
+            "--bg" => palette.bg = color(&mut iter, arg)?,
+
+            flag if flag.starts_with("--") => return Err(format!("Unknown option: {}", flag)),
+
+            path => {
+                if rom_path.replace(path.to_string()).is_some() {
+                    return Err("Only one game path may be given".to_string());
+                }
+            }
+        }
+    }
+
+    Ok(Options {
+        rom_path: rom_path.ok_or("Missing game path")?,
+        pixel_shape,
+        profile,
+        scale,
+        ipf,
+        ips,
+        turbo,
+        interlace,
+        ghosting,
+        crt,
+        skip_unknown,
+        save_faults,
+        vsync,
+        volume,
+        beep_hz,
+        beep_wave,
+        palette,
+        record_scale,
+        keymap_path,
+        dump_start,
+    })
+}
+
+// Fetch the value following a flag.
+fn value<'a>(
+    iter: &mut impl Iterator<Item = &'a String>,
+    flag: &str,
+) -> Result<&'a String, String> {
+    iter.next()
+        .ok_or_else(|| format!("Missing value for {}", flag))
+}
+
+// Fetch and parse the numeric value following a flag.
+fn number<'a, T: FromStr>(
+    iter: &mut impl Iterator<Item = &'a String>,
+    flag: &str,
+) -> Result<T, String> {
+    let raw = value(iter, flag)?;
+
+    raw.parse()
+        .map_err(|_| format!("Invalid value for {}: {}", flag, raw))
+}
+
+// Fetch and parse the RRGGBB hex color following a flag.
+fn color<'a>(
+    iter: &mut impl Iterator<Item = &'a String>,
+    flag: &str,
+) -> Result<Color, String> {
+    let raw = value(iter, flag)?;
+    let invalid = || format!("Invalid color for {}, expected RRGGBB: {}", flag, raw);
+
+    if raw.len() != 6 || !raw.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(invalid());
+    }
+
+    let rgb = u32::from_str_radix(raw, 16).map_err(|_| invalid())?;
+
+    Ok(Color::RGB((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8))
+}
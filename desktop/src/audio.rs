@@ -0,0 +1,70 @@
+use std::f32::consts::TAU;
+
+use sdl2::audio::{AudioCallback, AudioDevice, AudioSpecDesired};
+use sdl2::AudioSubsystem;
+
+// Shape of the beep, square is the harshest and sine the softest.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Waveform {
+    Square,
+    Sine,
+    Triangle,
+}
+
+impl Waveform {
+    // Sample at a phase from 0.0 to 1.0, ranging from -1.0 to 1.0.
+    fn sample(self, phase: f32) -> f32 {
+        match self {
+            Waveform::Square => {
+                if phase < 0.5 {
+                    1.0
+                } else {
+                    -1.0
+                }
+            }
+            Waveform::Sine => (phase * TAU).sin(),
+            Waveform::Triangle => 1.0 - 4.0 * (phase - 0.5).abs(),
+        }
+    }
+}
+
+// Tone generator fed to SDL's audio thread.
+pub struct Beep {
+    wave: Waveform,
+    phase_inc: f32,
+    phase: f32,
+    volume: f32,
+}
+
+impl AudioCallback for Beep {
+    type Channel = f32;
+
+    fn callback(&mut self, out: &mut [f32]) {
+        for sample in out.iter_mut() {
+            *sample = self.wave.sample(self.phase) * self.volume;
+            self.phase = (self.phase + self.phase_inc) % 1.0;
+        }
+    }
+}
+
+// Open a paused audio device playing a beep at the given frequency, volume ranges from 0.0 to 1.0.
+// Resume the device while the sound timer is active and pause it otherwise.
+pub fn open_beeper(
+    audio: &AudioSubsystem,
+    volume: f32,
+    hz: u32,
+    wave: Waveform,
+) -> Result<AudioDevice<Beep>, String> {
+    let spec = AudioSpecDesired {
+        freq: Some(44_100),
+        channels: Some(1),
+        samples: None,
+    };
+
+    audio.open_playback(None, &spec, |spec| Beep {
+        wave,
+        phase_inc: hz as f32 / spec.freq as f32,
+        phase: 0.0,
+        volume,
+    })
+}
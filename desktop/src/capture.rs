@@ -0,0 +1,139 @@
+// Saving the screen to image files, and the machine state for bug reports.
+
+use std::fs::File;
+use std::io::BufWriter;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use chip8_core::state::{Reproducer, SaveState};
+use chip8_core::{SCREEN_HEIGHT, SCREEN_WIDTH};
+use gif::{Encoder, Frame, Repeat};
+use image::{Rgb, RgbImage};
+
+use crate::args::Palette;
+
+// Write the screen to a PNG in the working directory, scaled and colored the same as the window.
+// Returns the file name, which is timestamped so screenshots don't overwrite each other.
+pub fn save_screenshot(
+    screen: &[u8],
+    width: usize,
+    scale: u32,
+    palette: &Palette,
+) -> Result<String, String> {
+    let height = screen.len() / width;
+    let scale = (scale * SCREEN_WIDTH as u32 / width as u32).max(1);
+    let image = RgbImage::from_fn(width as u32 * scale, height as u32 * scale, |x, y| {
+        let pixel = screen[(x / scale) as usize + width * (y / scale) as usize];
+        let color = palette.color(pixel);
+
+        Rgb([color.r, color.g, color.b])
+    });
+
+    let path = format!("screenshot-{}.png", timestamp());
+    image.save(&path).map_err(|err| err.to_string())?;
+
+    Ok(path)
+}
+
+// Shortest GIF frame delay in hundredths of a second, browsers play shorter delays much slower than asked.
+const MIN_DELAY: u32 = 2;
+
+// Frames captured for an animated GIF, kept in memory and only encoded once the recording stops.
+#[derive(Default)]
+pub struct Recording {
+    // Each distinct frame's pixels and width, and for how many emulated frames it stayed on screen. The resolution
+    // can change during a recording.
+    frames: Vec<(Vec<u8>, usize, u32)>,
+}
+
+impl Recording {
+    // Call once per emulated frame with whether the screen changed since the last call. Unchanged frames only
+    // extend how long the previous one is shown.
+    pub fn push(&mut self, screen: &[u8], width: usize, changed: bool) {
+        match self.frames.last_mut() {
+            Some((_, _, duration)) if !changed => *duration += 1,
+            _ => self.frames.push((screen.to_vec(), width, 1)),
+        }
+    }
+
+    // Encode the frames as a looping GIF in the working directory and return its file name. Timing follows the
+    // emulator's 60 frames per second, but frames shown for less than MIN_DELAY are dropped, so fast animations
+    // play at up to 50 frames per second at the right speed. Frames are sized to the low resolution screen times
+    // the scale, so hi-res frames are downscaled at 1.
+    pub fn save(&self, scale: u32, palette: &Palette) -> Result<String, String> {
+        let out_width = SCREEN_WIDTH * scale as usize;
+        let out_height = SCREEN_HEIGHT * scale as usize;
+        // Pixels are color indices, so the GIF's palette maps them straight to the screen colors.
+        let colors: Vec<u8> = (0..4)
+            .flat_map(|pixel| {
+                let color = palette.color(pixel);
+                [color.r, color.g, color.b]
+            })
+            .collect();
+
+        let path = format!("recording-{}.gif", timestamp());
+        let file = File::create(&path).map_err(|err| err.to_string())?;
+        let mut encoder = Encoder::new(file, out_width as u16, out_height as u16, &colors)
+            .map_err(|err| err.to_string())?;
+        encoder
+            .set_repeat(Repeat::Infinite)
+            .map_err(|err| err.to_string())?;
+
+        // Time in emulated frames up to the end of the current frame, and in hundredths of a second up to the end of
+        // the last written one.
+        let mut elapsed = 0;
+        let mut written = 0;
+
+        for (i, (screen, width, duration)) in self.frames.iter().enumerate() {
+            elapsed += duration;
+            let delay = elapsed * 100 / 60 - written;
+            let last = i + 1 == self.frames.len();
+            if delay < MIN_DELAY && !last {
+                // The next frame is shown in its place.
+                continue;
+            }
+            written += delay;
+
+            let height = screen.len() / width;
+            let pixels: Vec<u8> = (0..out_width * out_height)
+                .map(|idx| {
+                    let x = idx % out_width * width / out_width;
+                    let y = idx / out_width * height / out_height;
+                    screen[x + width * y]
+                })
+                .collect();
+
+            let mut frame =
+                Frame::from_indexed_pixels(out_width as u16, out_height as u16, pixels, None);
+            frame.delay = delay.max(MIN_DELAY) as u16;
+            encoder.write_frame(&frame).map_err(|err| err.to_string())?;
+        }
+
+        Ok(path)
+    }
+}
+
+// Write the full machine state, including the screen and held keys, as JSON in the working directory and return the
+// file name.
+pub fn save_state_json(state: &SaveState) -> Result<String, String> {
+    let path = format!("state-{}.json", timestamp());
+    let file = File::create(&path).map_err(|err| err.to_string())?;
+    serde_json::to_writer_pretty(BufWriter::new(file), state).map_err(|err| err.to_string())?;
+
+    Ok(path)
+}
+
+// Write a fault reproducer as JSON in the working directory and return the file name.
+pub fn save_reproducer(reproducer: &Reproducer) -> Result<String, String> {
+    let path = format!("fault-{}.json", timestamp());
+    let file = File::create(&path).map_err(|err| err.to_string())?;
+    serde_json::to_writer(BufWriter::new(file), reproducer).map_err(|err| err.to_string())?;
+
+    Ok(path)
+}
+
+// Milliseconds since the Unix epoch, for file names.
+fn timestamp() -> u128 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_millis())
+}
@@ -0,0 +1,107 @@
+// Step debugger panel, drawn over the game while emulation is stopped in debug mode.
+
+use chip8_core::disasm::disassemble_range;
+use chip8_core::Emu;
+use sdl2::pixels::Color;
+use sdl2::rect::Rect;
+use sdl2::render::{BlendMode, Canvas};
+use sdl2::video::Window;
+
+use crate::text::{draw_text, CHAR_WIDTH, LINE_HEIGHT};
+
+const MARGIN: i32 = 8;
+// Width of the panel in characters, enough for the longest mnemonic next to an address and opcode.
+const PANEL_COLUMNS: i32 = 32;
+const TEXT_COLOR: Color = Color::RGB(255, 255, 255);
+const PC_COLOR: Color = Color::RGB(255, 255, 0);
+// Instructions shown before and after the program counter.
+const DISASM_BEFORE: u16 = 3;
+const DISASM_AFTER: u16 = 8;
+const STACK_PER_LINE: usize = 6;
+
+// Draw the CPU state and the instructions around the program counter in the top left corner.
+pub fn draw(canvas: &mut Canvas<Window>, chip8: &Emu) {
+    let pc = chip8.get_pc();
+    let regs = chip8.get_registers();
+    let sp = chip8.get_sp() as usize;
+    let stack: Vec<String> = chip8
+        .get_stack()
+        .iter()
+        .take(sp)
+        .map(|addr| format!("{:03X}", addr))
+        .collect();
+
+    let mut lines = vec![
+        (
+            format!("PC {:04X}  I {:04X}  SP {:X}", pc, chip8.get_i(), sp),
+            TEXT_COLOR,
+        ),
+        (
+            format!(
+                "DT {:02X}  ST {:02X}",
+                chip8.get_delay_timer(),
+                chip8.get_sound_timer()
+            ),
+            TEXT_COLOR,
+        ),
+    ];
+
+    for (row, values) in regs.chunks(4).enumerate() {
+        let text = values
+            .iter()
+            .enumerate()
+            .map(|(col, value)| format!("V{:X} {:02X}", row * 4 + col, value))
+            .collect::<Vec<_>>()
+            .join("  ");
+        lines.push((text, TEXT_COLOR));
+    }
+
+    // Return addresses, oldest first and wrapped to fit the panel.
+    lines.push(("STACK".to_string(), TEXT_COLOR));
+    for entries in stack.chunks(STACK_PER_LINE) {
+        lines.push((format!("  {}", entries.join(" ")), TEXT_COLOR));
+    }
+    lines.push((String::new(), TEXT_COLOR));
+
+    // Instructions don't have to be aligned, so the lines before the program counter are a guess.
+    let start = pc.saturating_sub(DISASM_BEFORE * 2);
+    let end = pc.saturating_add(DISASM_AFTER * 2);
+    for (addr, op, mnemonic) in disassemble_range(chip8.get_ram(), start, end) {
+        let (marker, color) = if addr == pc {
+            ('>', PC_COLOR)
+        } else {
+            (' ', TEXT_COLOR)
+        };
+        lines.push((
+            format!("{} {:04X}  {:04X}  {}", marker, addr, op, mnemonic),
+            color,
+        ));
+    }
+
+    lines.push((String::new(), TEXT_COLOR));
+    lines.push(("F10 STEP  F7 RESUME".to_string(), TEXT_COLOR));
+
+    // Darken the game behind the panel so the text stays readable.
+    let height = lines.len() as i32 * LINE_HEIGHT + MARGIN * 2;
+    canvas.set_blend_mode(BlendMode::Blend);
+    canvas.set_draw_color(Color::RGBA(0, 0, 0, 200));
+    canvas
+        .fill_rect(Rect::new(
+            0,
+            0,
+            (PANEL_COLUMNS * CHAR_WIDTH + MARGIN * 2) as u32,
+            height as u32,
+        ))
+        .unwrap();
+    canvas.set_blend_mode(BlendMode::None);
+
+    for (i, (text, color)) in lines.iter().enumerate() {
+        draw_text(
+            canvas,
+            text,
+            MARGIN,
+            MARGIN + i as i32 * LINE_HEIGHT,
+            *color,
+        );
+    }
+}
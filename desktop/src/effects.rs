@@ -0,0 +1,72 @@
+// Post-processing applied to emulator frames before they are drawn.
+
+// Experimental flicker reduction simulating interlaced drawing: a pixel is shown if it was lit in either of the
+// last two frames, in the planes it was lit in, so sprites redrawn every other frame stay visible. Games that
+// deliberately flash pixels will look wrong with this.
+pub fn blend_frames(previous: &[u8], current: &[u8]) -> Vec<u8> {
+    previous
+        .iter()
+        .zip(current)
+        .map(|(&previous, &current)| previous | current)
+        .collect()
+}
+
+// Intensity lost per frame by a pixel that turned off, so it fades out over about six frames.
+const FADE_STEP: u8 = 43;
+
+// Phosphor persistence: pixels that turn off fade toward the background over a few frames instead of vanishing at
+// once, which makes flickering sprites much easier to watch.
+#[derive(Default)]
+pub struct Ghosting {
+    // Per pixel brightness from 0 to 255, and the color index it had while lit.
+    intensity: Vec<u8>,
+    colors: Vec<u8>,
+}
+
+impl Ghosting {
+    // Advance the fade by one frame, call once per emulated frame.
+    pub fn update(&mut self, screen: &[u8]) {
+        // Start over when the resolution changes, the old pixels don't line up anymore.
+        if self.intensity.len() != screen.len() {
+            self.intensity = vec![0; screen.len()];
+            self.colors = vec![0; screen.len()];
+        }
+
+        for (i, &pixel) in screen.iter().enumerate() {
+            if pixel != 0 {
+                self.intensity[i] = u8::MAX;
+                self.colors[i] = pixel;
+            } else {
+                self.intensity[i] = self.intensity[i].saturating_sub(FADE_STEP);
+            }
+        }
+    }
+
+    // Pixels that turned off but still glow, as (index, color index while lit, intensity). Draw them before the lit
+    // pixels, which cover any that came back on since the last update.
+    pub fn fading(&self) -> impl Iterator<Item = (usize, u8, u8)> + '_ {
+        self.intensity
+            .iter()
+            .zip(&self.colors)
+            .enumerate()
+            .filter(|(_, (&intensity, _))| intensity > 0 && intensity < u8::MAX)
+            .map(|(i, (&intensity, &color))| (i, color, intensity))
+    }
+
+    pub fn is_fading(&self) -> bool {
+        self.fading().next().is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn blend_keeps_pixels_lit_in_either_frame() {
+        let previous = [0, 1, 0, 2, 3, 1];
+        let current = [0, 0, 1, 1, 0, 2];
+
+        assert_eq!(blend_frames(&previous, &current), vec![0, 1, 1, 3, 3, 3]);
+    }
+}
@@ -0,0 +1,85 @@
+// Memory viewer panel, a scrollable hex dump of RAM drawn over the right side of the game.
+
+use chip8_core::Emu;
+use sdl2::pixels::Color;
+use sdl2::rect::Rect;
+use sdl2::render::{BlendMode, Canvas};
+use sdl2::video::Window;
+
+use crate::text::{draw_text, CHAR_WIDTH, LINE_HEIGHT};
+
+const MARGIN: i32 = 8;
+const BYTES_PER_ROW: usize = 16;
+// Rows shown at once, also how far a page scrolls.
+pub const PAGE_ROWS: isize = 16;
+// An address, two spaces and three characters per byte.
+const PANEL_COLUMNS: i32 = 6 + BYTES_PER_ROW as i32 * 3;
+const TEXT_COLOR: Color = Color::RGB(255, 255, 255);
+const PC_COLOR: Color = Color::RGB(255, 255, 0);
+const I_COLOR: Color = Color::RGB(0, 255, 255);
+
+pub struct HexDump {
+    // First row shown, always a multiple of BYTES_PER_ROW.
+    start: usize,
+}
+
+impl HexDump {
+    pub fn new(start: u16) -> Self {
+        Self {
+            start: start as usize / BYTES_PER_ROW * BYTES_PER_ROW,
+        }
+    }
+
+    // Scroll by a number of rows, negative scrolls up. Stops at either end of RAM.
+    pub fn scroll(&mut self, rows: isize, ram_len: usize) {
+        let last = ram_len.saturating_sub(PAGE_ROWS as usize * BYTES_PER_ROW);
+        let offset = rows * BYTES_PER_ROW as isize;
+
+        self.start = self.start.saturating_add_signed(offset).min(last);
+    }
+
+    // Draw one page of RAM, with the bytes at PC in yellow and the byte at I in cyan.
+    pub fn draw(&mut self, canvas: &mut Canvas<Window>, chip8: &Emu) {
+        let ram = chip8.get_ram();
+        let pc = chip8.get_pc() as usize;
+        let i = chip8.get_i() as usize;
+        // The RAM can be smaller than the start given on the command line.
+        self.scroll(0, ram.len());
+
+        let width = (PANEL_COLUMNS * CHAR_WIDTH + MARGIN * 2) as u32;
+        let height = (PAGE_ROWS as i32 * LINE_HEIGHT + MARGIN * 2) as u32;
+        let left = canvas.viewport().width() as i32 - width as i32;
+
+        // Darken the game behind the panel so the text stays readable.
+        canvas.set_blend_mode(BlendMode::Blend);
+        canvas.set_draw_color(Color::RGBA(0, 0, 0, 200));
+        canvas.fill_rect(Rect::new(left, 0, width, height)).unwrap();
+        canvas.set_blend_mode(BlendMode::None);
+
+        for (row, bytes) in ram[self.start..]
+            .chunks(BYTES_PER_ROW)
+            .take(PAGE_ROWS as usize)
+            .enumerate()
+        {
+            let addr = self.start + row * BYTES_PER_ROW;
+            let y = MARGIN + row as i32 * LINE_HEIGHT;
+            draw_text(
+                canvas,
+                &format!("{:04X}", addr),
+                left + MARGIN,
+                y,
+                TEXT_COLOR,
+            );
+
+            for (col, byte) in bytes.iter().enumerate() {
+                let color = match addr + col {
+                    a if a == pc || a == pc + 1 => PC_COLOR,
+                    a if a == i => I_COLOR,
+                    _ => TEXT_COLOR,
+                };
+                let x = left + MARGIN + (6 + col as i32 * 3) * CHAR_WIDTH;
+                draw_text(canvas, &format!("{:02X}", byte), x, y, color);
+            }
+        }
+    }
+}
@@ -0,0 +1,101 @@
+// Mapping from keyboard keys and controller buttons to the 16 Chip-8 keys.
+//
+// Keymap files hold one `key = value` line per key, e.g. `W = 0x5`. Keys use SDL's key names, quoted if they
+// contain spaces like `"Left Shift" = 0x0`, and values are Chip-8 keys from 0x0 to 0xF. Controller buttons use
+// SDL's button names with a `pad:` prefix, e.g. `pad:dpup = 0x2` or `pad:leftshoulder = 0x1`. Blank lines and lines
+// starting with # are ignored. This is a subset of TOML.
+
+use std::collections::HashMap;
+use std::fs;
+
+use sdl2::controller::Button;
+use sdl2::keyboard::Keycode;
+
+pub struct Keymap {
+    pub keys: HashMap<Keycode, usize>,
+    pub buttons: HashMap<Button, usize>,
+}
+
+impl Default for Keymap {
+    // Built-in layout, the left side of a QWERTZ keyboard laid out like the COSMAC VIP keypad. On controllers the
+    // D-pad is 2/4/6/8, the directions most games use, and the face buttons are common action keys.
+    fn default() -> Self {
+        let keys = HashMap::from([
+            (Keycode::Num1, 0x1),
+            (Keycode::Num2, 0x2),
+            (Keycode::Num3, 0x3),
+            (Keycode::Num4, 0xC),
+            (Keycode::Q, 0x4),
+            (Keycode::W, 0x5),
+            (Keycode::E, 0x6),
+            (Keycode::R, 0xD),
+            (Keycode::A, 0x7),
+            (Keycode::S, 0x8),
+            (Keycode::D, 0x9),
+            (Keycode::F, 0xE),
+            (Keycode::Y, 0xA),
+            (Keycode::X, 0x0),
+            (Keycode::C, 0xB),
+            (Keycode::V, 0xF),
+        ]);
+        let buttons = HashMap::from([
+            (Button::DPadUp, 0x2),
+            (Button::DPadLeft, 0x4),
+            (Button::DPadRight, 0x6),
+            (Button::DPadDown, 0x8),
+            (Button::A, 0x5),
+            (Button::B, 0x0),
+            (Button::X, 0xA),
+            (Button::Y, 0xB),
+            (Button::Back, 0xE),
+            (Button::Start, 0xF),
+        ]);
+
+        Self { keys, buttons }
+    }
+}
+
+// Load a keymap file on top of the built-in layout, keys it doesn't mention keep their default mapping.
+pub fn load(path: &str) -> Result<Keymap, String> {
+    let contents =
+        fs::read_to_string(path).map_err(|err| format!("Unable to open {}: {}", path, err))?;
+    let mut map = Keymap::default();
+
+    for (number, line) in contents.lines().enumerate() {
+        let line = line.trim();
+
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let invalid = |reason: &str| format!("{}:{}: {}", path, number + 1, reason);
+        let (name, value) = line
+            .split_once('=')
+            .ok_or_else(|| invalid("expected key = value"))?;
+        let name = name.trim().trim_matches('"');
+        let value = value.trim();
+
+        let button = match value.strip_prefix("0x") {
+            Some(hex) => usize::from_str_radix(hex, 16),
+            None => value.parse(),
+        }
+        .ok()
+        .filter(|&button| button < 16)
+        .ok_or_else(|| invalid(&format!("invalid Chip-8 key {}", value)))?;
+
+        match name.strip_prefix("pad:") {
+            Some(pad) => {
+                let pad_button = Button::from_string(pad)
+                    .ok_or_else(|| invalid(&format!("unknown controller button {}", pad)))?;
+                map.buttons.insert(pad_button, button);
+            }
+            None => {
+                let key = Keycode::from_name(name)
+                    .ok_or_else(|| invalid(&format!("unknown key {}", name)))?;
+                map.keys.insert(key, button);
+            }
+        }
+    }
+
+    Ok(map)
+}
@@ -1,51 +1,171 @@
+mod args;
+mod audio;
+mod capture;
+mod debugger;
+mod effects;
+mod hexdump;
+mod keymap;
+mod text;
+
+use std::collections::HashMap;
 use std::env;
-use std::fs::File;
+use std::fs::{self, File};
 use std::io::Read;
+use std::thread;
+use std::time::{Duration, Instant};
+use args::{Options, PixelShape, ProfileChoice};
+use effects::Ghosting;
+use hexdump::HexDump;
 use chip8_core::*;
-use sdl2::event::Event;
-use sdl2::pixels::Color;
+use chip8_core::compat::{detect_preset, CompatPreset, Profile};
+use chip8_core::timing::Pacer;
+use text::draw_text;
+use sdl2::event::{Event, WindowEvent};
+use sdl2::pixels::{Color, PixelFormatEnum};
 use sdl2::rect::Rect;
-use sdl2::render::Canvas;
-use sdl2::video::Window;
+use sdl2::render::{BlendMode, Canvas, Texture, TextureCreator};
+use sdl2::video::{FullscreenType, Window, WindowContext};
 use sdl2::keyboard::Keycode;
 
+// Rate of the delay and sound timers, which the game's speed is tied to.
+const FRAMES_PER_SECOND: u32 = 60;
+const FRAME_TIME: Duration = Duration::from_nanos(1_000_000_000 / FRAMES_PER_SECOND as u64);
+// How long a toast message stays on screen.
+const TOAST_TIME: Duration = Duration::from_secs(2);
+const TOAST_COLOR: Color = Color::RGB(255, 255, 0);
+
 fn main() {
     let args: Vec<_> = env::args().collect();
+    let options = match args::parse(&args) {
+        Ok(options) => options,
+        Err(msg) => {
+            println!("{}", msg);
+            println!("{}", args::USAGE);
 
-    if args.len() != 2 {
-        println!("Usage: cargo run path/to/game");
+            return;
+        }
+    };
 
-        return;
-    }
+    let keymap = match &options.keymap_path {
+        Some(path) => match keymap::load(path) {
+            Ok(keymap) => keymap,
+            Err(msg) => {
+                println!("{}", msg);
+
+                return;
+            }
+        },
+        None => keymap::Keymap::default(),
+    };
 
     // Scale screen size up for desktop.
-    const SCALE: u32 = 15;
-    const WINDOW_WIDTH: u32 = (SCREEN_WIDTH as u32) * SCALE;
-    const WINDOW_HEIGHT: u32 = (SCREEN_HEIGHT as u32) * SCALE;
-    const TICKS_PER_FRAME: usize = 10;
+    let window_width = (SCREEN_WIDTH as u32) * options.scale;
+    let window_height = (SCREEN_HEIGHT as u32) * options.scale;
 
     // Set up SDL2.
     let sdl_context = sdl2::init().unwrap();
     let video_subsystem = sdl_context.video().unwrap();
     let window = video_subsystem
-        .window("Chip-8 Emulator", WINDOW_WIDTH, WINDOW_HEIGHT)
+        .window("Chip-8 Emulator", window_width, window_height)
         .position_centered()
+        .resizable()
         .opengl()
         .build()
         .unwrap();
-    let mut canvas = window.into_canvas().present_vsync().build().unwrap();
+    let mut canvas = if options.vsync {
+        window.into_canvas().present_vsync().build().unwrap()
+    } else {
+        window.into_canvas().build().unwrap()
+    };
     canvas.clear();
     canvas.present();
 
+    // Prebuild a texture for round pixels so drawing stays a single copy per pixel. It is sized for the initial
+    // window and stretched when the window is resized.
+    let texture_creator = canvas.texture_creator();
+    let mut pixel_texture = match options.pixel_shape {
+        PixelShape::Square => None,
+        PixelShape::Circle => Some(circle_texture(&texture_creator, options.scale)),
+    };
+
+    // Play a beep while the sound timer is active, the game still runs without an audio device.
+    let beeper = sdl_context
+        .audio()
+        .and_then(|audio| audio::open_beeper(&audio, options.volume, options.beep_hz, options.beep_wave))
+        .map_err(|err| println!("Audio disabled: {}", err))
+        .ok();
+
+    // Controllers are opened as they're connected, SDL also reports the ones already connected at startup.
+    // The game is still playable with the keyboard without controller support.
+    let controller_subsystem = sdl_context
+        .game_controller()
+        .map_err(|err| println!("Controllers disabled: {}", err))
+        .ok();
+    let mut controllers = HashMap::new();
+
     // Listen for quit event and break loop.
     let mut event_pump = sdl_context.event_pump().unwrap();
     let mut chip8 = Emu::new();
+    // Stop with an error rather than crashing on an unknown opcode, unless asked to skip them.
+    chip8.set_unknown_opcodes(if options.skip_unknown {
+        UnknownOpcodes::Skip
+    } else {
+        UnknownOpcodes::Error
+    });
 
-    let mut rom = File::open(&args[1]).expect("Unable to open file");
+    let mut rom = File::open(&options.rom_path).expect("Unable to open file");
     let mut buffer = Vec::new();
 
     rom.read_to_end(&mut buffer).unwrap();
-    chip8.load(&buffer);
+
+    if let Err(err) = chip8.load(&buffer) {
+        println!("Unable to load {}: {}", options.rom_path, err);
+
+        return;
+    }
+    apply_profile(&mut chip8, options.profile, &buffer);
+
+    // With --ips the instruction count per frame carries fractions over instead of being fixed.
+    let mut pacer = options.ips.map(Pacer::new);
+    // Timers and the game run on a fixed 60Hz timestep, however often the loop draws. Each iteration runs as many
+    // emulated frames as are due since the last one, so a faster monitor doesn't speed up the game.
+    let mut frame_clock = Pacer::new(FRAMES_PER_SECOND);
+    match options.ips {
+        Some(ips) => println!("Running at {} instructions per second", ips),
+        None => println!("Running at {} instructions per frame", options.ipf),
+    }
+    let mut last_frame = Instant::now();
+    // Previous frame's pixels, used to highlight changes when toggled on.
+    let mut highlight_changes = false;
+    let mut previous_frame = chip8.get_display();
+    // Drawing is skipped while the screen stays the same, these force the next frame to be drawn.
+    let mut redraw = true;
+    let mut was_dirty = false;
+    let mut ghosting = options.ghosting.then(Ghosting::default);
+    // While paused the window keeps redrawing and handling input, but the game doesn't run.
+    let mut paused = false;
+    // Debug mode also stops the game, showing the CPU state and stepping one instruction at a time.
+    let mut debugging = false;
+    // Held Tab runs more instructions per emulated frame. Frames still come due at 60Hz so timers keep their
+    // speed, and the game's logic runs faster only as far as it's limited by instructions rather than timers.
+    let mut turbo = false;
+    // Size of the window before going fullscreen, restored when leaving it.
+    let mut windowed_size: Option<(u32, u32)> = None;
+    // Memory viewer, scrolled with the arrow and page keys while shown.
+    let mut show_dump = false;
+    let mut hex_dump = HexDump::new(options.dump_start);
+    // Frames captured since F9 started a recording.
+    let mut recording: Option<capture::Recording> = None;
+    // Frames since the stats in the title were last updated and the cycle count back then, shown when toggled on
+    // with F3.
+    let mut show_stats = false;
+    let mut stats_start = Instant::now();
+    let mut stats_frames = 0;
+    let mut stats_cycles = chip8.cycles();
+    // Preset last picked with F6, the first press starts from CosmacVip.
+    let mut preset: Option<CompatPreset> = None;
+    // Short message drawn over the game, with the time it was shown.
+    let mut toast: Option<(String, Instant)> = None;
 
     'gameloop: loop {
         for evt in event_pump.poll_iter() {
@@ -54,14 +174,185 @@ fn main() {
                     break 'gameloop;
                 }
 
+                Event::KeyDown { keycode: Some(Keycode::F5), repeat: false, .. } => {
+                    highlight_changes = !highlight_changes;
+                }
+
+                Event::KeyDown { keycode: Some(Keycode::F3), repeat: false, .. } => {
+                    show_stats = !show_stats;
+
+                    if !show_stats {
+                        let _ = canvas.window_mut().set_title("Chip-8 Emulator");
+                    }
+                }
+
+                Event::KeyDown { keycode: Some(Keycode::P), repeat: false, .. } => {
+                    paused = !paused;
+                }
+
+                Event::KeyDown { keycode: Some(Keycode::Tab), .. } => turbo = true,
+
+                Event::KeyUp { keycode: Some(Keycode::Tab), .. } => turbo = false,
+
+                Event::KeyDown { keycode: Some(Keycode::F4), repeat: false, .. } => {
+                    show_dump = !show_dump;
+                }
+
+                Event::KeyDown { keycode: Some(key @ (Keycode::Up | Keycode::Down | Keycode::PageUp | Keycode::PageDown)), .. }
+                    if show_dump =>
+                {
+                    let rows = match key {
+                        Keycode::Up => -1,
+                        Keycode::Down => 1,
+                        Keycode::PageUp => -hexdump::PAGE_ROWS,
+                        _ => hexdump::PAGE_ROWS,
+                    };
+
+                    hex_dump.scroll(rows, chip8.get_ram().len());
+                }
+
+                // Try the next quirk preset. Quirks are only read while an instruction runs, so swapping them between
+                // frames leaves the game in a consistent state.
+                Event::KeyDown { keycode: Some(Keycode::F6), repeat: false, .. } => {
+                    let next = preset.map_or(CompatPreset::CosmacVip, CompatPreset::next);
+                    chip8.apply_profile(Profile::from(next));
+                    preset = Some(next);
+                    toast = Some((format!("Quirks: {:?}", next), Instant::now()));
+                }
+
+                Event::KeyDown { keycode: Some(Keycode::F7), repeat: false, .. } => {
+                    debugging = !debugging;
+                }
+
+                // Step a single instruction, timers don't run while stepping.
+                Event::KeyDown { keycode: Some(Keycode::F10), .. } if debugging => {
+                    if let Err(err) = chip8.tick() {
+                        report_fault(&chip8, &buffer, err, options.save_faults);
+
+                        break 'gameloop;
+                    }
+                }
+
+                Event::KeyDown { keycode: Some(Keycode::F11), repeat: false, .. } => {
+                    let window = canvas.window_mut();
+                    let result = match windowed_size.take() {
+                        Some((width, height)) => window
+                            .set_fullscreen(FullscreenType::Off)
+                            .and_then(|()| window.set_size(width, height).map_err(|err| err.to_string())),
+                        None => {
+                            let size = window.size();
+                            window
+                                .set_fullscreen(FullscreenType::Desktop)
+                                .map(|()| windowed_size = Some(size))
+                        }
+                    };
+
+                    if let Err(err) = result {
+                        eprintln!("Unable to toggle fullscreen: {}", err);
+                    }
+                }
+
+                Event::KeyDown { keycode: Some(Keycode::F12), repeat: false, .. } => {
+                    match capture::save_screenshot(&chip8.get_display(), chip8.display_width(), options.scale, &options.palette) {
+                        Ok(path) => println!("Saved screenshot to {}", path),
+                        Err(err) => eprintln!("Unable to save screenshot: {}", err),
+                    }
+                }
+
+                // Dump the machine state for bug reports.
+                Event::KeyDown { keycode: Some(Keycode::F8), repeat: false, .. } => {
+                    match capture::save_state_json(&chip8.save_state()) {
+                        Ok(path) => println!("Saved state to {}", path),
+                        Err(err) => eprintln!("Unable to save state: {}", err),
+                    }
+                }
+
+                // Start a recording, or stop it and write the GIF.
+                Event::KeyDown { keycode: Some(Keycode::F9), repeat: false, .. } => match recording.take() {
+                    Some(finished) => match finished.save(options.record_scale, &options.palette) {
+                        Ok(path) => println!("Saved recording to {}", path),
+                        Err(err) => eprintln!("Unable to save recording: {}", err),
+                    },
+                    None => {
+                        recording = Some(capture::Recording::default());
+                        println!("Recording started, press F9 again to stop");
+                    }
+                },
+
+                // Restart the game, reset wipes RAM so the game has to be loaded again.
+                Event::KeyDown { keycode: Some(Keycode::Backspace), repeat: false, .. } => {
+                    chip8.reset();
+                    // Can't fail, the buffer always holds a game that loaded before.
+                    let _ = chip8.load(&buffer);
+                }
+
+                // The window contents may have been lost, e.g. after being covered or resized.
+                Event::Window { win_event: WindowEvent::Exposed | WindowEvent::SizeChanged(..), .. } => {
+                    redraw = true;
+                }
+
+                // Switch to a game dropped onto the window, keeping the current one if it can't be loaded.
+                Event::DropFile { filename, .. } => match fs::read(&filename) {
+                    Ok(data) => {
+                        chip8.reset();
+
+                        match chip8.load(&data) {
+                            Ok(()) => {
+                                apply_profile(&mut chip8, options.profile, &data);
+                                buffer = data;
+                            }
+                            Err(err) => {
+                                println!("Unable to load {}: {}", filename, err);
+                                let _ = chip8.load(&buffer);
+                            }
+                        }
+                    }
+                    Err(err) => println!("Unable to open {}: {}", filename, err),
+                },
+
                 Event::KeyDown { keycode: Some(key), .. } => {
-                    if let Some(k) = key2btn(key) {
+                    if let Some(&k) = keymap.keys.get(&key) {
                         chip8.keypress(k, true);
                     }
                 }
 
                 Event::KeyUp { keycode: Some(key), .. } => {
-                    if let Some(k) = key2btn(key) {
+                    if let Some(&k) = keymap.keys.get(&key) {
+                        chip8.keypress(k, false);
+                    }
+                }
+
+                Event::ControllerDeviceAdded { which, .. } => {
+                    if let Some(subsystem) = &controller_subsystem {
+                        match subsystem.open(which) {
+                            Ok(controller) => {
+                                println!("Connected controller {}", controller.name());
+                                controllers.insert(controller.instance_id(), controller);
+                            }
+                            Err(err) => println!("Unable to open controller: {}", err),
+                        }
+                    }
+                }
+
+                // Release everything the controller could have held, its button up events won't come anymore.
+                Event::ControllerDeviceRemoved { which, .. } => {
+                    if let Some(controller) = controllers.remove(&which) {
+                        println!("Disconnected controller {}", controller.name());
+
+                        for &k in keymap.buttons.values() {
+                            chip8.keypress(k, false);
+                        }
+                    }
+                }
+
+                Event::ControllerButtonDown { button, .. } => {
+                    if let Some(&k) = keymap.buttons.get(&button) {
+                        chip8.keypress(k, true);
+                    }
+                }
+
+                Event::ControllerButtonUp { button, .. } => {
+                    if let Some(&k) = keymap.buttons.get(&button) {
                         chip8.keypress(k, false);
                     }
                 }
@@ -70,56 +361,278 @@ fn main() {
             }
         }
 
-        // Redraw screen only after a certain amount of ticks.
-        for _ in 0..TICKS_PER_FRAME {
-            chip8.tick();
+        let due_frames = frame_clock.budget(last_frame.elapsed());
+        last_frame = Instant::now();
+
+        // Frames that come due while paused are dropped, so unpausing doesn't run them all at once.
+        if !paused && !debugging && !chip8.is_halted() {
+            for _ in 0..due_frames {
+                // Instructions run per emulated frame.
+                let mut ticks = match &mut pacer {
+                    Some(pacer) => pacer.budget(FRAME_TIME),
+                    None => options.ipf,
+                };
+                if turbo {
+                    ticks *= options.turbo;
+                }
+
+                match chip8.tick_n(ticks) {
+                    // Keep the window open on the last frame, the game can still be restarted or replaced.
+                    Ok(StepResult::Halted) => {
+                        println!("Game exited, press Backspace to restart");
+
+                        break;
+                    }
+                    Ok(_) => (),
+                    Err(err) => {
+                        report_fault(&chip8, &buffer, err, options.save_faults);
+
+                        break 'gameloop;
+                    }
+                }
+
+                chip8.tick_timers();
+
+                if let Some(ghosting) = &mut ghosting {
+                    ghosting.update(&chip8.get_display());
+                }
+
+                if let Some(recording) = &mut recording {
+                    let changed = chip8.take_display_changed();
+                    recording.push(&chip8.get_display(), chip8.display_width(), changed);
+                }
+            }
+        }
+
+        if let Some(beeper) = &beeper {
+            if chip8.is_beeping() && !paused && !debugging {
+                beeper.resume();
+            } else {
+                beeper.pause();
+            }
+        }
+
+        // The resolution can change mid game, frames from before the switch don't line up with the new one.
+        let display = chip8.get_display();
+        if previous_frame.len() != display.len() {
+            previous_frame = display.clone();
+        }
+
+        // Drop the toast once it expired, drawing the frame without it.
+        if toast.as_ref().is_some_and(|(_, shown)| shown.elapsed() >= TOAST_TIME) {
+            toast = None;
+            redraw = true;
+        }
+
+        // Panels change every frame, and interlacing or highlighting still differ one frame after the last change.
+        let dirty = chip8.take_display_dirty();
+        let fading = ghosting.as_ref().is_some_and(Ghosting::is_fading);
+        if dirty || was_dirty || redraw || debugging || show_dump || fading || toast.is_some() {
+            let width = chip8.display_width();
+            let blended;
+            let screen_buf = if options.interlace {
+                blended = effects::blend_frames(&previous_frame, &display);
+                blended.as_slice()
+            } else {
+                display.as_slice()
+            };
+            let previous = highlight_changes.then_some(previous_frame.as_slice());
+            draw_screen(
+                screen_buf,
+                (width, chip8.display_height()),
+                &mut canvas,
+                pixel_texture.as_mut(),
+                previous,
+                ghosting.as_ref(),
+                &options,
+            );
+            if debugging {
+                debugger::draw(&mut canvas, &chip8);
+            }
+            if show_dump {
+                hex_dump.draw(&mut canvas, &chip8);
+            }
+            if let Some((message, _)) = &toast {
+                draw_text(&mut canvas, message, 8, 8, TOAST_COLOR);
+            }
+            canvas.present();
+            previous_frame = display;
+            redraw = false;
+        } else {
+            // Nothing was presented, so vsync didn't wait either. Sleep for the rest of the frame instead of spinning.
+            thread::sleep(FRAME_TIME.saturating_sub(last_frame.elapsed()));
+        }
+        was_dirty = dirty;
+
+        // Only update the title about once per second so the numbers are readable.
+        stats_frames += 1;
+        let elapsed = stats_start.elapsed();
+        if elapsed >= Duration::from_secs(1) {
+            if show_stats {
+                let seconds = elapsed.as_secs_f64();
+                // Only instructions that actually ran count, tick_n can stop early. A reset restarts the count.
+                let stats_instructions = chip8.cycles().saturating_sub(stats_cycles);
+                let mut title = format!(
+                    "Chip-8 Emulator - {:.0} FPS, {:.0} IPS",
+                    stats_frames as f64 / seconds,
+                    stats_instructions as f64 / seconds
+                );
+                if chip8.unknown_opcode_count() > 0 {
+                    title += &format!(", {} unknown opcodes skipped", chip8.unknown_opcode_count());
+                }
+                let _ = canvas.window_mut().set_title(&title);
+            }
+
+            stats_start = Instant::now();
+            stats_frames = 0;
+            stats_cycles = chip8.cycles();
         }
+    }
 
-        chip8.tick_timers();
-        draw_screen(&chip8, &mut canvas);
+    // Tell the user why the game stopped, and with --save-faults write a reproducer they can attach to a bug report.
+    fn report_fault(chip8: &Emu, rom: &[u8], err: ExecError, save: bool) {
+        println!("Emulation stopped: {}", err);
+
+        if save {
+            match capture::save_reproducer(&chip8.reproducer(rom, err)) {
+                Ok(path) => println!("Saved reproducer to {}", path),
+                Err(err) => eprintln!("Unable to save reproducer: {}", err),
+            }
+        }
     }
 
-    fn draw_screen(emu: &Emu, canvas: &mut Canvas<Window>) {
-        // Clear canvas with black.
-        canvas.set_draw_color(Color::RGB(0, 0, 0));
+        // Apply the quirk profile chosen on the command line, guessing it from the game with --profile auto.
+    fn apply_profile(chip8: &mut Emu, choice: Option<ProfileChoice>, rom: &[u8]) {
+        let profile = match choice {
+            Some(ProfileChoice::Fixed(profile)) => profile,
+            Some(ProfileChoice::Auto) => {
+                let profile = Profile::from(detect_preset(rom));
+                println!("Using the {:?} profile", profile);
+                profile
+            }
+            None => return,
+        };
+
+        chip8.apply_profile(profile);
+    }
+
+    // Draw the screen, when the previous frame is given pixels that changed since then are highlighted.
+    // The screen is scaled by the largest whole factor that fits the window and centered, leaving borders in the
+    // background color when the window's proportions differ. Presenting is left to the caller so panels can be drawn
+    // on top.
+    fn draw_screen(
+        screen_buf: &[u8],
+        (width, height): (usize, usize),
+        canvas: &mut Canvas<Window>,
+        mut pixel_texture: Option<&mut Texture>,
+        previous: Option<&[u8]>,
+        ghosting: Option<&Ghosting>,
+        options: &Options,
+    ) {
+        // Highlight colors for pixels that turned on and pixels that just turned off.
+        const LIT_COLOR: Color = Color::RGB(0, 255, 0);
+        const CLEARED_COLOR: Color = Color::RGB(128, 0, 0);
+        let (window_width, window_height) = canvas.output_size().unwrap();
+        let scale = (window_width / width as u32).min(window_height / height as u32).max(1);
+        let left = (window_width as i32 - (width as u32 * scale) as i32) / 2;
+        let top = (window_height as i32 - (height as u32 * scale) as i32) / 2;
+        // Scanlines take a quarter of each row, when there's room for them.
+        let row_height = if options.crt && scale > 1 { scale - (scale / 4).max(1) } else { scale };
+        let palette = &options.palette;
+
+        // Clear canvas with the background color.
+        canvas.set_draw_color(palette.bg);
         canvas.clear();
-        let screen_buf = emu.get_display();
-        // Now set draw color to white, iterate through each point and see if it should be drawn.
-        canvas.set_draw_color(Color::RGB(255, 255, 255));
+
+        // Fading pixels go first, pixels lit since the last ghosting update are drawn over them.
+        for (i, pixel, intensity) in ghosting.into_iter().flat_map(Ghosting::fading) {
+            let x = (i % width) as u32;
+            let y = (i / width) as u32;
+            if y as usize >= height {
+                continue;
+            }
+
+            let rect = Rect::new(left + (x * scale) as i32, top + (y * scale) as i32, scale, row_height);
+            let color = fade(palette.color(pixel), palette.bg, intensity);
+            fill_pixel(canvas, pixel_texture.as_deref_mut(), rect, color);
+        }
+
+        // Square pixels without highlighting can be drawn a whole run at a time.
+        if previous.is_none() && pixel_texture.is_none() {
+            for (y, x, len) in display_spans(screen_buf, width) {
+                canvas.set_draw_color(palette.color(screen_buf[x + width * y]));
+                let rect = Rect::new(
+                    left + x as i32 * scale as i32,
+                    top + y as i32 * scale as i32,
+                    len as u32 * scale,
+                    row_height,
+                );
+                canvas.fill_rect(rect).unwrap();
+            }
+
+            return;
+        }
+
+        // Iterate through each point and see if it should be drawn.
         for (i, pixel) in screen_buf.iter().enumerate() {
-            if *pixel {
-                // Convert our 1D array index into a 2D (x,y) position.
-                let x = (i % SCREEN_WIDTH) as u32;
-                let y = (i / SCREEN_WIDTH) as u32;
-                // Draw a rectangle at (x,y), scaled up by the SCALE value.
-                let rect = Rect::new((x * SCALE) as i32, (y * SCALE) as i32, SCALE, SCALE);
+            let lit = *pixel != 0;
+            let was_lit = previous.map_or(lit, |previous| previous[i] != 0);
+            let color = match (lit, was_lit) {
+                (true, true) => palette.color(*pixel),
+                (true, false) => LIT_COLOR,
+                (false, true) => CLEARED_COLOR,
+                (false, false) => continue,
+            };
+            // Convert our 1D array index into a 2D (x,y) position.
+            let x = (i % width) as u32;
+            let y = (i / width) as u32;
+            // Draw a rectangle at (x,y), scaled up to fill the window.
+            let rect = Rect::new(left + (x * scale) as i32, top + (y * scale) as i32, scale, row_height);
+            fill_pixel(canvas, pixel_texture.as_deref_mut(), rect, color);
+        }
+    }
 
+    // Draw one pixel in the given color, with the round pixel texture if there is one.
+    fn fill_pixel(canvas: &mut Canvas<Window>, pixel_texture: Option<&mut Texture>, rect: Rect, color: Color) {
+        match pixel_texture {
+            Some(texture) => {
+                texture.set_color_mod(color.r, color.g, color.b);
+                canvas.copy(texture, None, rect).unwrap();
+            }
+            None => {
+                canvas.set_draw_color(color);
                 canvas.fill_rect(rect).unwrap();
             }
         }
+    }
 
-        canvas.present();
+    // Mix a color with the background, intensity 255 is the full color and 0 the background.
+    fn fade(color: Color, bg: Color, intensity: u8) -> Color {
+        let mix = |fg: u8, bg: u8| (bg as i32 + (fg as i32 - bg as i32) * intensity as i32 / 255) as u8;
+
+        Color::RGB(mix(color.r, bg.r), mix(color.g, bg.g), mix(color.b, bg.b))
     }
 
-    fn key2btn(key: Keycode) -> Option<usize> {
-        match key {
-            Keycode::Num1 => Some(0x1),
-            Keycode::Num2 => Some(0x2),
-            Keycode::Num3 => Some(0x3),
-            Keycode::Num4 => Some(0xC),
-            Keycode::Q => Some(0x4),
-            Keycode::W => Some(0x5),
-            Keycode::E => Some(0x6),
-            Keycode::R => Some(0xD),
-            Keycode::A => Some(0x7),
-            Keycode::S => Some(0x8),
-            Keycode::D => Some(0x9),
-            Keycode::F => Some(0xE),
-            Keycode::Y => Some(0xA),
-            Keycode::X => Some(0x0),
-            Keycode::C => Some(0xB),
-            Keycode::V => Some(0xF),
-            _ => None,
+    // Build a white filled circle on a transparent background, sized to one scaled pixel.
+    fn circle_texture(creator: &TextureCreator<WindowContext>, size: u32) -> Texture<'_> {
+        let mut texture = creator
+            .create_texture_static(PixelFormatEnum::RGBA32, size, size)
+            .unwrap();
+        let radius = size as f32 / 2.0;
+        let mut pixels = vec![0u8; (size * size * 4) as usize];
+
+        for (i, pixel) in pixels.chunks_exact_mut(4).enumerate() {
+            let dx = (i as u32 % size) as f32 + 0.5 - radius;
+            let dy = (i as u32 / size) as f32 + 0.5 - radius;
+
+            if dx * dx + dy * dy <= radius * radius {
+                pixel.copy_from_slice(&[255, 255, 255, 255]);
+            }
         }
+
+        texture.update(None, &pixels, (size * 4) as usize).unwrap();
+        texture.set_blend_mode(BlendMode::Blend);
+
+        texture
     }
 }
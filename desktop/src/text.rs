@@ -0,0 +1,86 @@
+// A tiny built-in font for debug panels, so drawing text doesn't need SDL_ttf or a font file.
+// Glyphs are 3x5 pixels, one row per byte with the leftmost pixel in bit 2. Only upper case letters, digits and the
+// punctuation the disassembler uses are covered, anything else is drawn as a space.
+
+use sdl2::pixels::Color;
+use sdl2::rect::Rect;
+use sdl2::render::Canvas;
+use sdl2::video::Window;
+
+// Window pixels per font pixel.
+pub const TEXT_SCALE: u32 = 2;
+// Size of one character cell, including a pixel of spacing to the right and below.
+pub const CHAR_WIDTH: i32 = 4 * TEXT_SCALE as i32;
+pub const LINE_HEIGHT: i32 = 7 * TEXT_SCALE as i32;
+
+fn glyph(c: char) -> [u8; 5] {
+    match c.to_ascii_uppercase() {
+        '0' => [7, 5, 5, 5, 7],
+        '1' => [2, 6, 2, 2, 7],
+        '2' => [7, 1, 7, 4, 7],
+        '3' => [7, 1, 7, 1, 7],
+        '4' => [5, 5, 7, 1, 1],
+        '5' => [7, 4, 7, 1, 7],
+        '6' => [7, 4, 7, 5, 7],
+        '7' => [7, 1, 2, 2, 2],
+        '8' => [7, 5, 7, 5, 7],
+        '9' => [7, 5, 7, 1, 7],
+        'A' => [2, 5, 7, 5, 5],
+        'B' => [6, 5, 6, 5, 6],
+        'C' => [3, 4, 4, 4, 3],
+        'D' => [6, 5, 5, 5, 6],
+        'E' => [7, 4, 6, 4, 7],
+        'F' => [7, 4, 6, 4, 4],
+        'G' => [3, 4, 5, 5, 3],
+        'H' => [5, 5, 7, 5, 5],
+        'I' => [7, 2, 2, 2, 7],
+        'J' => [1, 1, 1, 5, 2],
+        'K' => [5, 5, 6, 5, 5],
+        'L' => [4, 4, 4, 4, 7],
+        'M' => [5, 7, 7, 5, 5],
+        'N' => [6, 5, 5, 5, 5],
+        'O' => [2, 5, 5, 5, 2],
+        'P' => [6, 5, 6, 4, 4],
+        'Q' => [2, 5, 5, 6, 3],
+        'R' => [6, 5, 6, 5, 5],
+        'S' => [3, 4, 2, 1, 6],
+        'T' => [7, 2, 2, 2, 2],
+        'U' => [5, 5, 5, 5, 7],
+        'V' => [5, 5, 5, 5, 2],
+        'W' => [5, 5, 7, 7, 5],
+        'X' => [5, 5, 2, 5, 5],
+        'Y' => [5, 5, 2, 2, 2],
+        'Z' => [7, 1, 2, 4, 7],
+        ',' => [0, 0, 0, 2, 4],
+        '.' => [0, 0, 0, 0, 2],
+        ':' => [0, 2, 0, 2, 0],
+        '-' => [0, 0, 7, 0, 0],
+        '>' => [4, 2, 1, 2, 4],
+        '[' => [6, 4, 4, 4, 6],
+        ']' => [3, 1, 1, 1, 3],
+        _ => [0; 5],
+    }
+}
+
+// Draw a single line of text with its top left corner at (x, y).
+pub fn draw_text(canvas: &mut Canvas<Window>, text: &str, x: i32, y: i32, color: Color) {
+    canvas.set_draw_color(color);
+
+    for (col, c) in text.chars().enumerate() {
+        let left = x + col as i32 * CHAR_WIDTH;
+
+        for (row, bits) in glyph(c).iter().enumerate() {
+            for bit in 0..3 {
+                if bits & (4 >> bit) != 0 {
+                    let rect = Rect::new(
+                        left + bit * TEXT_SCALE as i32,
+                        y + row as i32 * TEXT_SCALE as i32,
+                        TEXT_SCALE,
+                        TEXT_SCALE,
+                    );
+                    canvas.fill_rect(rect).unwrap();
+                }
+            }
+        }
+    }
+}
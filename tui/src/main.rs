@@ -0,0 +1,202 @@
+use std::env;
+use std::fs;
+use std::io::{self, Stdout, Write};
+use std::time::{Duration, Instant};
+
+use chip8_core::*;
+use crossterm::cursor::{Hide, MoveTo, Show};
+use crossterm::event::{
+    self, Event, KeyCode, KeyEvent, KeyEventKind, KeyboardEnhancementFlags,
+    PopKeyboardEnhancementFlags, PushKeyboardEnhancementFlags,
+};
+use crossterm::terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen};
+use crossterm::{execute, queue};
+
+const FRAME_TIME: Duration = Duration::from_micros(16_667);
+const TICKS_PER_FRAME: usize = 10;
+// Most terminals only report key presses, so without release events a key counts as held until it hasn't repeated
+// for this long.
+const KEY_HOLD: Duration = Duration::from_millis(150);
+
+// Puts the terminal into raw mode on the alternate screen, and restores it when dropped, including on panic.
+struct Terminal {
+    out: Stdout,
+    release_events: bool,
+}
+
+impl Terminal {
+    fn enter() -> io::Result<Self> {
+        let mut out = io::stdout();
+        terminal::enable_raw_mode()?;
+        execute!(out, EnterAlternateScreen, Hide, Clear(ClearType::All))?;
+
+        // Ask for key release events where the terminal supports them.
+        let release_events = terminal::supports_keyboard_enhancement().unwrap_or(false);
+        if release_events {
+            execute!(
+                out,
+                PushKeyboardEnhancementFlags(KeyboardEnhancementFlags::REPORT_EVENT_TYPES)
+            )?;
+        }
+
+        Ok(Self {
+            out,
+            release_events,
+        })
+    }
+}
+
+impl Drop for Terminal {
+    fn drop(&mut self) {
+        if self.release_events {
+            let _ = execute!(self.out, PopKeyboardEnhancementFlags);
+        }
+
+        let _ = execute!(self.out, Show, LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+fn main() {
+    let args: Vec<_> = env::args().collect();
+    if args.len() != 2 {
+        println!("Usage: cargo run path/to/game");
+
+        return;
+    }
+
+    let mut chip8 = Emu::new();
+    // Stop on unknown opcodes like the desktop frontend, rather than panicking with the terminal in raw mode.
+    chip8.set_unknown_opcodes(UnknownOpcodes::Error);
+    let buffer = match fs::read(&args[1]) {
+        Ok(buffer) => buffer,
+        Err(err) => {
+            println!("Unable to open {}: {}", args[1], err);
+
+            return;
+        }
+    };
+
+    if let Err(err) = chip8.load(&buffer) {
+        println!("Unable to load {}: {}", args[1], err);
+
+        return;
+    }
+
+    let mut term = Terminal::enter().expect("Unable to set up the terminal");
+    // When each key was last pressed, only used without release events.
+    let mut pressed_at: [Option<Instant>; 16] = [None; 16];
+    let mut next_frame = Instant::now() + FRAME_TIME;
+    let mut redraw = true;
+
+    let result = 'gameloop: loop {
+        // Handle input until the next frame is due.
+        while event::poll(next_frame.saturating_duration_since(Instant::now())).unwrap_or(false) {
+            match event::read() {
+                Ok(Event::Key(KeyEvent {
+                    code: KeyCode::Esc, ..
+                })) => break 'gameloop Ok(()),
+
+                Ok(Event::Key(KeyEvent {
+                    code: KeyCode::Char(c),
+                    kind,
+                    ..
+                })) => {
+                    if let Some(k) = key2btn(c.to_ascii_lowercase()) {
+                        let pressed = kind != KeyEventKind::Release;
+                        chip8.keypress(k, pressed);
+                        pressed_at[k] = pressed.then(Instant::now);
+                    }
+                }
+
+                // Redraw everything, the terminal may have cleared or cut off the screen.
+                Ok(Event::Resize(..)) => redraw = true,
+
+                _ => (),
+            }
+        }
+
+        if !term.release_events {
+            for (k, at) in pressed_at.iter_mut().enumerate() {
+                if at.is_some_and(|at| at.elapsed() > KEY_HOLD) {
+                    chip8.keypress(k, false);
+                    *at = None;
+                }
+            }
+        }
+
+        match chip8.tick_n(TICKS_PER_FRAME) {
+            Ok(StepResult::Halted) => break 'gameloop Ok(()),
+            Ok(_) => (),
+            Err(err) => break 'gameloop Err(err),
+        }
+
+        chip8.tick_timers();
+        next_frame += FRAME_TIME;
+
+        if chip8.take_display_dirty() || redraw {
+            if draw_screen(&mut term.out, &chip8, redraw).is_err() {
+                break 'gameloop Ok(());
+            }
+
+            redraw = false;
+        }
+    };
+
+    drop(term);
+
+    if let Err(err) = result {
+        println!("Emulation stopped: {}", err);
+    }
+
+    // Draw two pixel rows per terminal row using half blocks.
+    fn draw_screen(out: &mut Stdout, chip8: &Emu, clear: bool) -> io::Result<()> {
+        let width = chip8.display_width();
+        let display = chip8.get_display();
+        let rows: Vec<&[u8]> = display.chunks(width).collect();
+
+        if clear {
+            queue!(out, Clear(ClearType::All))?;
+        }
+
+        for (y, pair) in rows.chunks(2).enumerate() {
+            let line: String = (0..width)
+                .map(
+                    |x| match (pair[0][x] != 0, pair.get(1).is_some_and(|row| row[x] != 0)) {
+                        (true, true) => '█',
+                        (true, false) => '▀',
+                        (false, true) => '▄',
+                        (false, false) => ' ',
+                    },
+                )
+                .collect();
+
+            queue!(out, MoveTo(0, y as u16))?;
+            out.write_all(line.as_bytes())?;
+        }
+
+        out.flush()
+    }
+
+    fn key2btn(key: char) -> Option<usize> {
+        match key {
+            '1' => Some(0x1),
+            '2' => Some(0x2),
+            '3' => Some(0x3),
+            '4' => Some(0xC),
+            'q' => Some(0x4),
+            'w' => Some(0x5),
+            'e' => Some(0x6),
+            'r' => Some(0xD),
+            'a' => Some(0x7),
+            's' => Some(0x8),
+            'd' => Some(0x9),
+            'f' => Some(0xE),
+            'y' => Some(0xA),
+            'x' => Some(0x0),
+            'c' => Some(0xB),
+            'v' => Some(0xF),
+            _ => None,
+        }
+    }
+}
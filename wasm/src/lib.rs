@@ -0,0 +1,79 @@
+// Browser bindings for the core, built with `wasm-pack build --target web` from this directory.
+// See www/ for an example page drawing the display on a canvas.
+
+use chip8_core::{Emu, UnknownOpcodes};
+use js_sys::Uint8Array;
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen]
+pub struct EmuWasm {
+    chip8: Emu,
+}
+
+#[wasm_bindgen]
+impl EmuWasm {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        let mut chip8 = Emu::new();
+        // A panic would abort the whole module, an error is thrown from tick instead.
+        chip8.set_unknown_opcodes(UnknownOpcodes::Error);
+
+        Self { chip8 }
+    }
+
+    // Execute one instruction, throwing if execution can't continue, e.g. on an unknown opcode.
+    pub fn tick(&mut self) -> Result<(), JsError> {
+        self.chip8.tick()?;
+
+        Ok(())
+    }
+
+    // Call 60 times per second, e.g. once per requestAnimationFrame.
+    pub fn tick_timers(&mut self) {
+        self.chip8.tick_timers();
+    }
+
+    pub fn keypress(&mut self, idx: usize, pressed: bool) {
+        self.chip8.keypress(idx, pressed);
+    }
+
+    // Load a game from its bytes, throwing if it doesn't fit in memory.
+    pub fn load(&mut self, data: &[u8]) -> Result<(), JsError> {
+        self.chip8.load(data)?;
+
+        Ok(())
+    }
+
+    pub fn reset(&mut self) {
+        self.chip8.reset();
+    }
+
+    // Copy of the display, one color index per pixel row by row, 0 for unlit.
+    pub fn display(&self) -> Uint8Array {
+        Uint8Array::from(self.chip8.get_display().as_slice())
+    }
+
+    // Current resolution, changes when a game switches to SUPER-CHIP high resolution.
+    pub fn screen_width(&self) -> usize {
+        self.chip8.display_width()
+    }
+
+    pub fn screen_height(&self) -> usize {
+        self.chip8.display_height()
+    }
+
+    pub fn is_beeping(&self) -> bool {
+        self.chip8.is_beeping()
+    }
+
+    // Whether the game exited, tick does nothing until it's reset.
+    pub fn is_halted(&self) -> bool {
+        self.chip8.is_halted()
+    }
+}
+
+impl Default for EmuWasm {
+    fn default() -> Self {
+        Self::new()
+    }
+}
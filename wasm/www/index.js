@@ -0,0 +1,78 @@
+// Example glue for the browser build. Build the package first with `wasm-pack build --target web` in wasm/,
+// then serve the wasm/ directory over HTTP and open www/index.html.
+import init, { EmuWasm } from "../pkg/wasm.js";
+
+// Instructions per frame, matching the desktop default.
+const TICKS_PER_FRAME = 10;
+// Same layout as the desktop frontend.
+const KEYS = {
+    "1": 0x1, "2": 0x2, "3": 0x3, "4": 0xC,
+    "q": 0x4, "w": 0x5, "e": 0x6, "r": 0xD,
+    "a": 0x7, "s": 0x8, "d": 0x9, "f": 0xE,
+    "y": 0xA, "x": 0x0, "c": 0xB, "v": 0xF,
+};
+// Colors for each pixel's color index, XO-CHIP games can use all four.
+const COLORS = ["#000000", "#FFFFFF", "#AAAAAA", "#555555"];
+
+await init();
+
+const chip8 = new EmuWasm();
+const canvas = document.getElementById("screen");
+const ctx = canvas.getContext("2d");
+let frame = null;
+
+document.getElementById("rom").addEventListener("change", async (event) => {
+    const file = event.target.files[0];
+    const data = new Uint8Array(await file.arrayBuffer());
+
+    if (frame !== null) {
+        cancelAnimationFrame(frame);
+    }
+
+    chip8.reset();
+    chip8.load(data);
+    frame = requestAnimationFrame(run);
+});
+
+document.addEventListener("keydown", (event) => {
+    if (event.key in KEYS) {
+        chip8.keypress(KEYS[event.key], true);
+    }
+});
+
+document.addEventListener("keyup", (event) => {
+    if (event.key in KEYS) {
+        chip8.keypress(KEYS[event.key], false);
+    }
+});
+
+function run() {
+    try {
+        for (let i = 0; i < TICKS_PER_FRAME; i++) {
+            chip8.tick();
+        }
+    } catch (err) {
+        console.error("Emulation stopped:", err);
+        return;
+    }
+
+    chip8.tick_timers();
+    draw();
+    frame = requestAnimationFrame(run);
+}
+
+function draw() {
+    const width = chip8.screen_width();
+    const display = chip8.display();
+    const scale = canvas.width / width;
+
+    ctx.fillStyle = COLORS[0];
+    ctx.fillRect(0, 0, canvas.width, canvas.height);
+
+    display.forEach((pixel, i) => {
+        if (pixel !== 0) {
+            ctx.fillStyle = COLORS[pixel];
+            ctx.fillRect((i % width) * scale, Math.floor(i / width) * scale, scale, scale);
+        }
+    });
+}